@@ -1,20 +1,56 @@
 // https://github.com/clap-rs/clap/blob/master/examples/git-derive.rs
 // https://docs.rs/clap/latest/clap/_derive/index.html#terminology
-use crate::config::{appConfig, Config, HealthCheckError};
-use anyhow::{bail, Result};
-use clap::{Parser, Subcommand};
-use npmpink_core::item_formatter::PackageItemFormatter;
-use npmpink_core::ops::packages::{difference_packages, packages_from_source};
+use crate::completions::{
+    complete_locked_packages, complete_source_ids, complete_source_packages, complete_source_paths,
+    write_registration, CompletionShell,
+};
+use crate::config::{
+    appConfig, update_config_doc, Config, ConfigKey, HealthCheckError, Mode, ResolvedConfig,
+    WorkspaceConfig,
+};
+use crate::dashboard::TargetDashboard;
+use crate::dirs::set_config_file_override;
+use crate::output::{
+    is_added, is_linked, CheckOutput, CliError, ErrorCode, ErrorOutput, PackageOutput,
+    SourceOutput, SyncAllOutput, SyncFailure, SyncMethod, SyncOutput, SyncedMember, SyncedPackage,
+    TargetOutput,
+};
+use crate::preview::{find_package, package_preview};
+use crate::serve::{serve, ServeArgs};
+use anyhow::{bail, Context, Result};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Args, Parser, Subcommand};
+use clap_complete::engine::ArgValueCompleter;
+use npmpink_core::file_lock::{write_atomic, FileLock};
+use npmpink_core::git::{is_git_url, GitSource};
+use npmpink_core::item_display::PackageStatus;
+use npmpink_core::item_formatter::{PackageItemFormatter, SourceItemFormatter};
+use npmpink_core::ops::discover::{discover_source_candidates, SourceCandidate};
+use npmpink_core::ops::packages::{
+    difference_packages, discover_meta, is_glob_pattern, match_packages, packages_from_source,
+};
 use npmpink_core::package::Package;
-use npmpink_core::source::Source;
+use npmpink_core::pick_history::PickHistory;
+use npmpink_core::registry::Registry;
+use npmpink_core::source::{Source, SourceHealth};
+use npmpink_core::tarball::{copy_package, extract_package, is_tarball, tarball_paths};
 use npmpink_core::target::Target;
+use npmpink_core::target_registry::TargetRegistry;
 use npmpink_core::workspace::Workspace;
-use npmpink_tui::item::PackageItemDisplay;
-use npmpink_tui::select::pick_items;
-use npmpink_tui::shell::shell;
+use npmpink_tui::dashboard::run_dashboard;
+use npmpink_tui::item::{PackageItemDisplay, SourceItemDisplay};
+use npmpink_tui::select::{
+    confirm, pick_items, pick_items_with_selected, InputPickerConfig, PickConfig, PickerMode,
+    PreviewCommand,
+};
+use npmpink_tui::shell::{shell, Shell};
+use serde_json::json;
 use std::cell::{RefCell, RefMut};
-use std::path::PathBuf;
-use std::process::Command;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
 use std::rc::Rc;
 
 #[derive(Parser, Debug)]
@@ -28,10 +64,51 @@ pub(crate) struct Cli {
         help = "Current workspace dir to run cli"
     )]
     cwd: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Config file to use, default to $NPMPINK_HOME/config.json or $XDG_CONFIG_HOME/npmpink/config.json, data and cache dirs are put next to it"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "Print JSON output for scripts and editors, errors are printed as {\"error\": {\"code\", \"message\"}}"
+    )]
+    json: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Pick items listed in file instead of the picker, one name per line, `-` for stdin"
+    )]
+    select: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_parser = PossibleValuesParser::new(["fzf", "builtin", "inquire", "input"]).map(parse_picker_mode),
+        help = "Picker to use instead of the configured one"
+    )]
+    picker: Option<PickerMode>,
     #[clap(skip)]
     target: Option<RefCell<Target>>,
 }
 
+fn parse_picker_mode(value: String) -> PickerMode {
+    serde_json::from_value(value.into()).expect("picker mode of possible values")
+}
+
+/// The upstream is sent back as `Location` header, so only plain http urls.
+fn parse_upstream(value: &str) -> Result<String, String> {
+    if !(value.starts_with("http://") || value.starts_with("https://")) {
+        return Err("expect an http:// or https:// url".to_owned());
+    }
+    if !value.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err("expect an url of ascii characters without spaces".to_owned());
+    }
+    Ok(value.to_owned())
+}
+
 impl Cli {
     pub(super) fn target(&self) -> RefMut<'_, Target> {
         self.target.as_ref().unwrap().borrow_mut()
@@ -61,18 +138,136 @@ pub(super) enum Commands {
     /// Check packages in current workspace.
     Check,
 
+    /// Show config.
+    Config {
+        #[command(subcommand)]
+        command: ConfigSubCli,
+    },
+
     /// Sync added packages to node_modules
     Sync,
+
+    /// Projects that packages are added to or synced in.
+    Targets {
+        #[command(subcommand)]
+        command: TargetsSubCli,
+    },
+
+    /// Serve packages of sources as local npm registry.
+    Serve {
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(long, default_value_t = 4873)]
+        port: u16,
+        #[arg(
+            long,
+            value_parser = parse_upstream,
+            help = "Redirect requests of packages not in sources to this registry, like https://registry.npmjs.org"
+        )]
+        upstream: Option<String>,
+    },
+
+    /// Full screen dashboard of current workspace, to add, remove and sync
+    /// packages.
+    Tui,
+
+    /// Print script to register shell completions, like `source <(npk completions bash)`.
+    Completions { shell: CompletionShell },
+
+    /// Print details of package for previews of pickers.
+    #[command(name = "__preview", hide = true)]
+    Preview {
+        /// `<source id>:<package name>`
+        id: String,
+    },
 }
 
 #[derive(Debug, Subcommand)]
 pub(super) enum SourceSubCli {
-    /// Add source.
-    Add { dir: String },
-    /// Remove source.
-    Remove { dir: String },
+    /// Add source, dir can be git repository url or path if `--ref` is given.
+    Add {
+        dir: String,
+        #[arg(
+            long = "ref",
+            help = "Branch, tag or commit to checkout from git repository"
+        )]
+        git_ref: Option<String>,
+    },
+    /// Remove source, picked from the registered sources if no dir is given.
+    Remove {
+        #[arg(add = ArgValueCompleter::new(complete_source_paths))]
+        dir: Option<String>,
+    },
     /// List source.
     List,
+    /// Find workspaces and packages under dir, pick the ones to add as sources.
+    Discover {
+        /// Root dir to scan, default to current workspace dir.
+        dir: Option<PathBuf>,
+        #[arg(long, default_value_t = 4, help = "Max depth of dirs to scan")]
+        depth: usize,
+    },
+    /// Report missing or unreadable sources and locked packages that use them.
+    Check,
+    /// Move source to new dir, update config and packages in lockfile.
+    Move {
+        #[arg(add = ArgValueCompleter::new(complete_source_paths))]
+        old: PathBuf,
+        new: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum ConfigSubCli {
+    /// Print the global config, or the config resolved for current workspace.
+    Show {
+        #[arg(
+            long,
+            help = "Merge npmpink.config.json and package.json#npmpink of current workspace over global config"
+        )]
+        resolved: bool,
+    },
+    /// Print paths of config file, data and cache dirs.
+    Paths,
+    /// Print value of the key in global config.
+    Get {
+        key: ConfigKey,
+        #[arg(short, long, help = "Use npmpink.config.json of current workspace")]
+        workspace: bool,
+    },
+    /// Set value of the key, `members` and `pre_sync` take multiple values.
+    Set {
+        key: ConfigKey,
+        #[arg(required = true)]
+        values: Vec<String>,
+        #[arg(short, long, help = "Use npmpink.config.json of current workspace")]
+        workspace: bool,
+    },
+    /// Remove the key, keys of global config are reset to default.
+    Unset {
+        key: ConfigKey,
+        #[arg(short, long, help = "Use npmpink.config.json of current workspace")]
+        workspace: bool,
+    },
+    /// Open config file with $VISUAL or $EDITOR, it's saved only if valid.
+    Edit {
+        #[arg(short, long, help = "Use npmpink.config.json of current workspace")]
+        workspace: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub(super) enum TargetsSubCli {
+    /// List known targets.
+    List {
+        #[arg(long, help = "Only targets with the package in their lockfile")]
+        package: Option<String>,
+    },
+    /// Sync packages in all known targets.
+    SyncAll {
+        #[arg(long, help = "Only targets with the package in their lockfile")]
+        package: Option<String>,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -80,19 +275,87 @@ pub(super) enum SourceSubCli {
 pub(super) enum PackageSubCli {
     /// Manually add package by name to current workspace.
     /// The package must be within the sources.
-    Add,
+    #[command(mut_arg("names", |arg| arg.add(ArgValueCompleter::new(complete_source_packages))))]
+    Add(PackageArgs),
     /// Manually remove previously added package from current workspace.
     /// The package must be within the sources.
-    Remove,
+    #[command(mut_arg("names", |arg| arg.add(ArgValueCompleter::new(complete_locked_packages))))]
+    Remove(PackageArgs),
+    /// Pick packages with the added ones pre-selected, then add the newly
+    /// selected and remove the deselected in one step.
+    Edit {
+        #[arg(
+            long,
+            help = "Only packages of the source, by path or id",
+            add = ArgValueCompleter::new(complete_source_ids)
+        )]
+        source: Option<String>,
+        #[arg(
+            long,
+            help = "Pick the packages picked the last time in this project again"
+        )]
+        last: bool,
+    },
     /// list all
     List,
 }
 
+/// Packages to add or remove, picked interactively if no names are given.
+#[derive(Debug, Args)]
+pub(super) struct PackageArgs {
+    #[arg(help = "Package names or glob patterns, like @acme/*")]
+    names: Vec<String>,
+    #[arg(
+        long,
+        help = "Only packages of the source, by path or id",
+        add = ArgValueCompleter::new(complete_source_ids)
+    )]
+    source: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "Don't ask to confirm packages matched by glob patterns"
+    )]
+    yes: bool,
+    #[arg(
+        long,
+        conflicts_with = "names",
+        help = "Pick the packages picked the last time in this project again"
+    )]
+    last: bool,
+}
+
 pub(super) fn run() -> Result<()> {
     let mut cli = Cli::parse();
+    shell()?.set_json(cli.json);
     if cli.cwd.is_none() {
         cli.cwd = std::env::current_dir().ok();
     }
+    if let Some(ref config) = cli.config {
+        set_config_file_override(std::path::absolute(config)?);
+    }
+    if let Some(legacy) = Config::migrate_legacy()? {
+        shell()?.info(format!(
+            "migrated {} to {}",
+            legacy.display(),
+            Config::root_config_path().display()
+        ))?;
+    }
+    // commands should not run with an invalid config, which may be overwritten.
+    if !matches!(
+        cli.command,
+        Some(Commands::Init { .. })
+            | Some(Commands::Check)
+            | Some(Commands::Completions { .. })
+            | Some(Commands::Config {
+                command: ConfigSubCli::Edit { .. } | ConfigSubCli::Paths
+            })
+    ) {
+        match Config::healthcheck() {
+            Ok(_) | Err(HealthCheckError::ConfigFileNotExist) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
     cli.target = Some(RefCell::new(Target::init_from_dir(
         cli.cwd.as_ref().unwrap(),
     )));
@@ -102,17 +365,43 @@ pub(super) fn run() -> Result<()> {
             return cmd_handler_init(&InitArgs { force: *force });
         }
         Some(Commands::Source { command }) => {
-            return cmd_handler_source_sub_cli(command);
+            return cmd_handler_source_sub_cli(&cli, command);
         }
         Some(Commands::Package { command }) => {
             return cmd_handler_package_sub_cli(&cli, command);
         }
         Some(Commands::Check) => {
-            return cmd_handler_check();
+            return cmd_handler_check(&cli);
+        }
+        Some(Commands::Config { command }) => {
+            return cmd_handler_config_sub_cli(&cli, command);
         }
         Some(Commands::Sync) => {
             return cmd_handler_sync(&cli);
         }
+        Some(Commands::Targets { command }) => {
+            return cmd_handler_targets_sub_cli(command);
+        }
+        Some(Commands::Serve {
+            host,
+            port,
+            upstream,
+        }) => {
+            return cmd_handler_serve(&ServeArgs {
+                host: host.clone(),
+                port: *port,
+                upstream: upstream.clone(),
+            });
+        }
+        Some(Commands::Tui) => {
+            return cmd_handler_tui(&cli);
+        }
+        Some(Commands::Completions { shell }) => {
+            return write_registration(*shell, &mut std::io::stdout()).map_err(Into::into);
+        }
+        Some(Commands::Preview { id }) => {
+            return cmd_handler_preview(&cli, id);
+        }
         None => {}
     }
 
@@ -127,13 +416,17 @@ fn cmd_handler_init(args: &InitArgs) -> Result<()> {
     match result {
         Err(err) => match err {
             HealthCheckError::ConfigFileNotExist => {}
+            _ if args.force => {}
             _ => {
-                return Err(anyhow::Error::msg(err));
+                return Err(anyhow::Error::new(err)
+                    .context("fix the config file or run init with --force to recreate it"));
             }
         },
         Ok(_) => {
             if !args.force {
-                shell()?.info("npmpink is already initialized")?;
+                let mut sh = shell()?;
+                sh.info("npmpink is already initialized")?;
+                sh.json(&json!({ "config_file": Config::root_config_path(), "created": false }))?;
 
                 return Ok(());
             }
@@ -143,54 +436,557 @@ fn cmd_handler_init(args: &InitArgs) -> Result<()> {
     // init config
     Config::create_from_default()?;
 
-    shell()?.info("inited config file")
+    let mut sh = shell()?;
+    sh.info("inited config file")?;
+    sh.json(&json!({ "config_file": Config::root_config_path(), "created": true }))
 }
 
 /// Update packages inside npmpink.lock to node modules
 fn cmd_handler_sync(cli: &Cli) -> Result<()> {
     let target = cli.target();
-    let lockfile_pkgs = {
-        let lockfile = target.lockfile()?;
-        lockfile.packages_iter().collect::<Vec<Package>>()
+    TargetRegistry::register(Config::targets_file(), target.workspace.absolute_dir()?)?;
+    let synced = sync_target(&target)?;
+    shell()?.json(&synced)
+}
+
+pub(super) fn sync_target(target: &Target) -> Result<SyncOutput> {
+    let (resolved, lockfile_pkgs) = {
+        let config = appConfig.lock().unwrap();
+        let mut pkgs = target.lockfile()?.packages_iter().collect::<Vec<Package>>();
+        discover_meta(&mut pkgs);
+        (ResolvedConfig::load(&config, &target.workspace.dir)?, pkgs)
     };
-    let pkgs_paths = lockfile_pkgs.iter();
     let mut sh = shell()?;
+    let mut output = SyncOutput {
+        target: target.workspace.dir.clone(),
+        pre_sync: resolved.pre_sync.clone(),
+        members: Vec::new(),
+    };
 
-    for pkg in pkgs_paths {
-        sh.info(format!("> Link package {}: \n", pkg.name))?;
-        Command::new("pnpm")
-            .args(["link", &pkg.dir])
-            .status()
-            .map(|_| ())
-            .map_err(anyhow::Error::msg)?;
-        sh.info("\n")?;
+    for script in resolved.pre_sync.iter() {
+        sh.info(format!("> Run {}", script))?;
+        let status = run_child(
+            &mut sh,
+            Command::new("sh")
+                .args(["-c", script])
+                .current_dir(&target.workspace.dir),
+        )?;
+        if !status.success() {
+            bail!(CliError::new(
+                ErrorCode::CommandFailed,
+                format!("pre sync script `{}` failed with {}", script, status)
+            ));
+        }
+    }
+
+    for member in resolved.members.iter() {
+        let member_dir = target.workspace.dir.join(member);
+        let mut synced = Vec::new();
+
+        for pkg in lockfile_pkgs.iter() {
+            let dest = member_dir.join("node_modules").join(&pkg.name);
+            let method = if is_tarball(&pkg.dir) {
+                sh.info(format!("> Extract package {}", pkg.name))?;
+                extract_package(&pkg.dir, dest)?;
+                SyncMethod::Extract
+            } else if resolved.mode == Mode::Copy {
+                sh.info(format!("> Copy package {}", pkg.name))?;
+                copy_package(&pkg.dir, dest)?;
+                SyncMethod::Copy
+            } else {
+                sh.info(format!("> Link package {}: \n", pkg.name))?;
+                let program = resolved.package_manager.program();
+                let status = run_child(
+                    &mut sh,
+                    Command::new(program)
+                        .args(["link", &pkg.dir])
+                        .current_dir(&member_dir),
+                )
+                .map_err(|e| {
+                    CliError::new(
+                        ErrorCode::CommandFailed,
+                        format!("failed to run `{}`: {}", program, e),
+                    )
+                })?;
+                if !status.success() {
+                    bail!(CliError::new(
+                        ErrorCode::CommandFailed,
+                        format!("`{} link {}` failed with {}", program, pkg.dir, status)
+                    ));
+                }
+                sh.info("\n")?;
+                SyncMethod::Link
+            };
+            synced.push(SyncedPackage {
+                name: pkg.name.clone(),
+                version: pkg.meta.as_ref().and_then(|m| m.version.clone()),
+                method,
+            });
+        }
+
+        output.members.push(SyncedMember {
+            dir: member_dir,
+            packages: synced,
+        });
+    }
+
+    Ok(output)
+}
+
+/// Run command of sync, it's output is printed through shell when the
+/// shell is logging, like in the dashboard.
+fn run_child(sh: &mut Shell, command: &mut Command) -> Result<ExitStatus> {
+    if sh.is_logging() {
+        let output = command.stdin(Stdio::null()).output()?;
+        for line in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .chain(String::from_utf8_lossy(&output.stderr).lines())
+        {
+            sh.print(line)?;
+        }
+        return Ok(output.status);
     }
+    // output of commands must not mix with the JSON output.
+    let stdout = match sh.is_json() {
+        true => Stdio::from(std::io::stderr()),
+        false => Stdio::inherit(),
+    };
+    Ok(command.stdout(stdout).status()?)
+}
 
+/// Remove package from `node_modules` of members of target, it stays in the
+/// lockfile and is put back by the next sync.
+pub(super) fn unsync_package(target: &Target, name: &str) -> Result<()> {
+    let resolved = {
+        let config = appConfig.lock().unwrap();
+        ResolvedConfig::load(&config, &target.workspace.dir)?
+    };
+    let mut removed = false;
+    for member in resolved.members.iter() {
+        // components drop the `.` of the root member.
+        let dest = target
+            .workspace
+            .dir
+            .join(member)
+            .join("node_modules")
+            .join(name)
+            .components()
+            .collect::<PathBuf>();
+        let Ok(metadata) = fs::symlink_metadata(&dest) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            fs::remove_dir_all(&dest)?;
+        } else {
+            fs::remove_file(&dest)?;
+        }
+        shell()?.info(format!("removed {}", dest.display()))?;
+        removed = true;
+    }
+    if !removed {
+        shell()?.warn(format!("{} is not synced", name))?;
+    }
     Ok(())
 }
 
-fn cmd_handler_check() -> Result<()> {
-    let result = Config::healthcheck();
+fn cmd_handler_tui(cli: &Cli) -> Result<()> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        bail!(CliError::new(
+            ErrorCode::InteractiveRequired,
+            "tui must run in a terminal"
+        ));
+    }
+    let dir = cli.target().workspace.absolute_dir()?;
+    run_dashboard(&mut TargetDashboard::new(dir))
+}
+
+fn cmd_handler_targets_sub_cli(command: &TargetsSubCli) -> Result<()> {
+    let registry = TargetRegistry::load(Config::targets_file())?;
+    let targets_of = |package: &Option<String>| match package {
+        Some(name) => registry.targets_with_package(name),
+        None => registry.existing_targets().cloned().collect(),
+    };
 
-    if result.is_ok() {
-        shell()?.info("all checks pass")?;
-        return Ok(());
+    match command {
+        TargetsSubCli::List { package } => {
+            let targets = targets_of(package);
+            let mut sh = shell()?;
+            if targets.is_empty() {
+                sh.warn("no targets found")?;
+            }
+            let mut output = Vec::new();
+            for dir in targets {
+                let (packages, error) = match Target::init_from_dir(&dir).lockfile() {
+                    Ok(lockfile) => {
+                        let count = lockfile.packages.len();
+                        sh.info(format!("{} ({} packages)", dir.display(), count))?;
+                        (count, None)
+                    }
+                    Err(e) => {
+                        sh.warn(format!("{} (broken: {:#})", dir.display(), e))?;
+                        (0, Some(ErrorOutput::from(&e)))
+                    }
+                };
+                output.push(TargetOutput {
+                    dir,
+                    packages,
+                    error,
+                });
+            }
+            sh.json(&output)?;
+        }
+        TargetsSubCli::SyncAll { package } => {
+            let targets = targets_of(package);
+            let mut output = SyncAllOutput {
+                synced: Vec::new(),
+                failed: Vec::new(),
+            };
+            for dir in targets.iter() {
+                shell()?.info(format!("Sync {}", dir.display()))?;
+                match sync_target(&Target::init_from_dir(dir)) {
+                    Ok(synced) => output.synced.push(synced),
+                    Err(e) => {
+                        shell()?.error(format!("{:#}", e))?;
+                        output.failed.push(SyncFailure {
+                            target: dir.clone(),
+                            error: ErrorOutput::from(&e),
+                        });
+                    }
+                }
+            }
+            shell()?.json(&output)?;
+            if !output.failed.is_empty() {
+                bail!(CliError::new(
+                    ErrorCode::CommandFailed,
+                    format!(
+                        "{} of {} targets failed to sync",
+                        output.failed.len(),
+                        targets.len()
+                    )
+                ));
+            }
+        }
     }
+    Ok(())
+}
+
+fn cmd_handler_serve(args: &ServeArgs) -> Result<()> {
+    let registry = {
+        let config = appConfig.lock().unwrap();
+        Registry::new(config.sources.iter().flat_map(packages_from_source))
+    };
+    for pkg in registry.conflicts() {
+        let served = registry.get(&pkg.name).map(|p| p.source_id.as_str());
+        shell()?.warn(format!(
+            "package {} of source {} is skipped, it's served from source {}",
+            pkg.name,
+            pkg.source_id,
+            served.unwrap_or_default()
+        ))?;
+    }
+    if registry.is_empty() {
+        shell()?.warn("no packages to serve")?;
+    }
+    shell()?.json(&json!({
+        "url": format!("http://{}:{}", args.host, args.port),
+        "packages": registry.len(),
+    }))?;
 
-    bail!("check pass failed")
+    serve(registry, args)
 }
 
-fn cmd_handler_source_sub_cli(command: &SourceSubCli) -> Result<()> {
+fn cmd_handler_check(cli: &Cli) -> Result<()> {
+    let mut sh = shell()?;
+    let mut problems = Vec::new();
+
+    match Config::healthcheck() {
+        Ok(_) => {}
+        Err(HealthCheckError::ConfigFileNotExist) => {
+            let message = "Config file does not exist, run `npk init` first";
+            sh.warn(message)?;
+            problems.push(ErrorOutput {
+                code: ErrorCode::ConfigNotFound,
+                message: message.to_owned(),
+            });
+        }
+        Err(e) => {
+            let e = anyhow::Error::new(e);
+            sh.error(&e)?;
+            problems.push(ErrorOutput::from(&e));
+        }
+    }
+
+    let config = Config::load_or_default();
+    if let Err(e) = ResolvedConfig::load(&config, &cli.target().workspace.dir) {
+        sh.error(format!("{:#}", e))?;
+        problems.push(ErrorOutput {
+            code: ErrorCode::ConfigInvalid,
+            message: format!("{:#}", e),
+        });
+    }
+
+    let ok = problems.is_empty();
+    sh.json(&CheckOutput { ok, problems })?;
+    if !ok {
+        bail!(CliError::new(ErrorCode::CheckFailed, "check pass failed"))
+    }
+
+    sh.info("all checks pass")
+}
+
+fn cmd_handler_config_sub_cli(cli: &Cli, command: &ConfigSubCli) -> Result<()> {
     match command {
-        SourceSubCli::Add { dir } => {
-            cmd_handler_source_add(dir)?;
+        ConfigSubCli::Show { resolved: false } => {
+            let config = appConfig.lock().unwrap();
+            let mut sh = shell()?;
+            sh.print(serde_json::to_string_pretty(&*config)?)?;
+            sh.json(&*config)?;
+        }
+        ConfigSubCli::Paths => {
+            let mut sh = shell()?;
+            sh.info(format!("config: {}", Config::root_config_path().display()))?;
+            sh.info(format!("data: {}", Config::data_dir().display()))?;
+            sh.info(format!("cache: {}", Config::cache_dir().display()))?;
+            sh.json(&json!({
+                "config_file": Config::root_config_path(),
+                "data_dir": Config::data_dir(),
+                "cache_dir": Config::cache_dir(),
+            }))?;
         }
+        ConfigSubCli::Show { resolved: true } => {
+            let config = appConfig.lock().unwrap();
+            let resolved = ResolvedConfig::load(&config, &cli.target().workspace.dir)?;
+            let values = serde_json::to_value(&resolved)?;
+            let mut sh = shell()?;
+            let mut output = serde_json::Map::new();
+
+            for (key, origin) in resolved.origins.iter() {
+                sh.info(format!("{}: {}  ({})", key, values[key], origin))?;
+                output.insert(
+                    key.to_string(),
+                    json!({ "value": values[key], "origin": origin }),
+                );
+            }
+            sh.json(&output)?;
+        }
+        ConfigSubCli::Get { key, workspace } => {
+            cmd_handler_config_get(cli, *key, *workspace)?;
+        }
+        ConfigSubCli::Set {
+            key,
+            values,
+            workspace,
+        } => {
+            let value = key.parse_values(values)?;
+            cmd_handler_config_update(cli, *key, Some(value), *workspace)?;
+        }
+        ConfigSubCli::Unset { key, workspace } => {
+            cmd_handler_config_update(cli, *key, None, *workspace)?;
+        }
+        ConfigSubCli::Edit { workspace } => {
+            cmd_handler_config_edit(cli, *workspace)?;
+        }
+    }
+    Ok(())
+}
+
+fn workspace_config_doc(dir: &Path) -> Result<serde_json::Value> {
+    let path = dir.join(WorkspaceConfig::FILE_NAME);
+    if !path.try_exists()? {
+        return Ok(serde_json::json!({}));
+    }
+    serde_json::from_str(&fs::read_to_string(&path)?)
+        .with_context(|| format!("invalid config {}", path.display()))
+}
+
+fn cmd_handler_config_get(cli: &Cli, key: ConfigKey, workspace: bool) -> Result<()> {
+    let doc = if workspace {
+        workspace_config_doc(&cli.target().workspace.dir)?
+    } else {
+        ensure_global_key(key)?;
+        serde_json::to_value(&*appConfig.lock().unwrap())?
+    };
+
+    let mut sh = shell()?;
+    match doc.get(key.name()) {
+        Some(serde_json::Value::Array(values)) => {
+            for value in values {
+                sh.print(value.as_str().map_or(value.to_string(), str::to_owned))?;
+            }
+        }
+        Some(serde_json::Value::String(value)) => sh.print(value)?,
+        Some(value) => sh.print(value.to_string())?,
+        None => bail!(CliError::new(
+            ErrorCode::NotFound,
+            format!(
+                "`{}` is not set in {}",
+                key.name(),
+                WorkspaceConfig::FILE_NAME
+            )
+        )),
+    }
+    sh.json(&json!({ "key": key.name(), "value": doc[key.name()] }))
+}
+
+fn ensure_global_key(key: ConfigKey) -> Result<()> {
+    if key.is_workspace_only() {
+        bail!(CliError::new(
+            ErrorCode::InvalidArgument,
+            format!(
+                "`{}` is only in workspace config, use --workspace",
+                key.name()
+            )
+        ));
+    }
+    Ok(())
+}
+
+/// Set or unset (value is None) the key.
+fn cmd_handler_config_update(
+    cli: &Cli,
+    key: ConfigKey,
+    value: Option<serde_json::Value>,
+    workspace: bool,
+) -> Result<()> {
+    let invalid = |e: anyhow::Error| CliError::new(ErrorCode::InvalidArgument, format!("{:#}", e));
+    if workspace {
+        let dir = cli.target().workspace.dir.clone();
+        let _lock = FileLock::acquire(dir.join(WorkspaceConfig::FILE_NAME))?;
+        let mut doc = workspace_config_doc(&dir)?;
+        let updated: WorkspaceConfig = update_config_doc(&mut doc, key, value).map_err(invalid)?;
+        updated.flush(&dir)?;
+        return shell()?.json(&json!({ "key": key.name(), "value": doc[key.name()] }));
+    }
+
+    ensure_global_key(key)?;
+    // unset keys of global config are reset to default.
+    let value = value.or_else(|| {
+        serde_json::to_value(Config::default())
+            .ok()
+            .and_then(|doc| doc.get(key.name()).cloned())
+    });
+
+    let value = appConfig.lock().unwrap().update(|config| {
+        let mut doc = serde_json::to_value(&*config)?;
+        *config = update_config_doc(&mut doc, key, value).map_err(invalid)?;
+        Ok(doc[key.name()].clone())
+    })?;
+    shell()?.json(&json!({ "key": key.name(), "value": value }))
+}
+
+/// Edit a temporary copy of the config file, so the config file is never
+/// left invalid.
+fn cmd_handler_config_edit(cli: &Cli, workspace: bool) -> Result<()> {
+    let path = if workspace {
+        cli.target().workspace.dir.join(WorkspaceConfig::FILE_NAME)
+    } else {
+        Config::root_config_path().clone()
+    };
+    // none if the file doesn't exist yet.
+    let saved = if path.try_exists()? {
+        Some(fs::read_to_string(&path)?)
+    } else {
+        None
+    };
+    let content = if let Some(ref saved) = saved {
+        saved.clone()
+    } else if workspace {
+        "{}\n".to_owned()
+    } else {
+        serde_json::to_string_pretty(&Config::default())?
+    };
+
+    let file_name = path.file_name().unwrap().to_string_lossy();
+    let temp = std::env::temp_dir().join(format!("npmpink-{}-{}", std::process::id(), file_name));
+    fs::write(&temp, &content)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_owned());
+    // the editor may come with args, like `code --wait`.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg("sh")
+        .arg(&temp)
+        .status()
+        .with_context(|| format!("failed to run editor `{}`", editor))?;
+    if !status.success() {
+        bail!(CliError::new(
+            ErrorCode::CommandFailed,
+            format!("editor `{}` exited with {}", editor, status)
+        ));
+    }
+
+    let edited = fs::read_to_string(&temp)?;
+    let valid = if workspace {
+        WorkspaceConfig::parse(&edited).map(|_| ())
+    } else {
+        Config::parse(&edited, &path)
+            .map(|_| ())
+            .map_err(Into::into)
+    };
+    if let Err(e) = valid {
+        let e = CliError::new(ErrorCode::ConfigInvalid, format!("{:#}", e));
+        return Err(anyhow::Error::new(e).context(format!(
+            "config is not saved, the edited file is kept at {}",
+            temp.display()
+        )));
+    }
+
+    let lock = FileLock::acquire(&path)?;
+    // commands like `config set` may have changed the file while editing.
+    let current = if path.try_exists()? {
+        Some(fs::read_to_string(&path)?)
+    } else {
+        None
+    };
+    if current != saved {
+        let e = CliError::new(
+            ErrorCode::Conflict,
+            format!("{} was changed while editing", path.display()),
+        );
+        return Err(anyhow::Error::new(e).context(format!(
+            "config is not saved, the edited file is kept at {}",
+            temp.display()
+        )));
+    }
+    write_atomic(&path, edited)?;
+    drop(lock);
+    fs::remove_file(&temp)?;
+    let mut sh = shell()?;
+    sh.info(format!("saved {}", path.display()))?;
+    sh.json(&json!({ "saved": path }))
+}
+
+fn cmd_handler_source_sub_cli(cli: &Cli, command: &SourceSubCli) -> Result<()> {
+    match command {
+        SourceSubCli::Add { dir, git_ref } => match git_ref {
+            Some(git_ref) => cmd_handler_source_add_git(cli, dir, git_ref)?,
+            None if is_git_url(dir) => {
+                bail!(CliError::new(
+                    ErrorCode::InvalidArgument,
+                    "--ref is required to add git repository as source"
+                ))
+            }
+            None => cmd_handler_source_add(dir)?,
+        },
         SourceSubCli::Remove { dir } => {
-            cmd_handler_source_remove(dir)?;
+            cmd_handler_source_remove(cli, dir.as_ref())?;
         }
         SourceSubCli::List => {
             cmd_handler_source_list()?;
         }
+        SourceSubCli::Discover { dir, depth } => {
+            let dir = dir.as_ref().or(cli.cwd.as_ref()).unwrap();
+            cmd_handler_source_discover(cli, dir, *depth)?;
+        }
+        SourceSubCli::Check => {
+            cmd_handler_source_check(cli)?;
+        }
+        SourceSubCli::Move { old, new } => {
+            cmd_handler_source_move(cli, old, new)?;
+        }
     }
     Ok(())
 }
@@ -198,69 +994,509 @@ fn cmd_handler_source_sub_cli(command: &SourceSubCli) -> Result<()> {
 fn cmd_handler_source_add(dir: &String) -> Result<()> {
     let wk = Workspace::init_from_dir(dir);
 
-    if !wk.has_package_json() {
-        bail!("workspace doesn't contains package.json");
-    }
-
     let Ok(mut config) = appConfig.lock() else {
         bail!("Failed to get app config");
     };
 
-    let Some(absolute_dir) = wk.absolute_dir().ok() else {
-        bail!("Not an valid directory");
+    let Some(absolute_dir) = wk.absolute_dir().ok().filter(|dir| dir.exists()) else {
+        bail!(CliError::new(ErrorCode::NotFound, "Not an valid directory"));
+    };
+
+    let source = if wk.has_package_json() {
+        Source::new(absolute_dir)
+    } else if !tarball_paths(&absolute_dir)?.is_empty() {
+        Source::new_tarball(absolute_dir)
+    } else {
+        bail!(CliError::new(
+            ErrorCode::InvalidArgument,
+            "workspace doesn't contains package.json or tarballs"
+        ));
     };
+    let output = SourceOutput::from(&source);
 
-    let source = Source::new(absolute_dir);
+    config.update(|config| {
+        if config.has_source(&source.id) {
+            bail!(CliError::new(
+                ErrorCode::AlreadyExists,
+                "Source already exists"
+            ));
+        }
+        config.sources.push(source);
+        Ok(())
+    })?;
+    shell()?.json(&output)
+}
 
-    if config.has_source(&source.id) {
-        bail!("Source already exists");
+/// Checkout the git ref under npmpink home and add the worktree as source,
+/// update the worktree if it is already added.
+fn cmd_handler_source_add_git(cli: &Cli, url: &str, git_ref: &str) -> Result<()> {
+    let url = if is_git_url(url) {
+        url.to_owned()
+    } else {
+        resolve_dir(cli, &PathBuf::from(url))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let git = GitSource::new(url, git_ref);
+    let worktree = git.materialize(&Config::git_dir())?;
+
+    let wk = Workspace::init_from_dir(&worktree);
+    if !wk.has_package_json() {
+        bail!(CliError::new(
+            ErrorCode::InvalidArgument,
+            "workspace doesn't contains package.json"
+        ));
     }
 
-    config.sources.push(source);
-    config.flush()?;
+    let mut config = appConfig.lock().unwrap();
+    let source = Source::new_git(wk.absolute_dir()?, git);
 
-    Ok(())
-}
+    let msg = format!(
+        "{} checked out at {}",
+        source.git.as_ref().unwrap(),
+        source.path.display()
+    );
+    let output = SourceOutput::from(&source);
+    config.update(|config| {
+        if !config.has_source(&source.id) {
+            config.sources.push(source);
+        }
+        Ok(())
+    })?;
 
-fn cmd_handler_source_remove(dir: &String) -> Result<()> {
-    let wk = Workspace::init_from_dir(dir);
+    let mut sh = shell()?;
+    sh.info(msg)?;
+    sh.json(&output)
+}
 
+fn cmd_handler_source_remove(cli: &Cli, dir: Option<&String>) -> Result<()> {
     let Ok(mut config) = appConfig.lock() else {
         bail!("Failed to get app config");
     };
-    let Some(absolute_dir) = wk.absolute_dir().ok() else {
-        bail!("Not an valid directory");
+
+    let Some(dir) = dir else {
+        let items = SourceItemDisplay::list(
+            config
+                .sources
+                .iter()
+                .map(SourceItemFormatter::new)
+                .collect(),
+        );
+        let picked = pick_items(&items, Some(pick_config(cli, &config, &cli.target())?))?
+            .into_iter()
+            .map(|item| item.raw.inner.path.clone())
+            .collect::<Vec<PathBuf>>();
+
+        let mut removed = Vec::new();
+        for path in picked.iter() {
+            removed.extend(remove_source(&mut config, path)?);
+        }
+        let mut sh = shell()?;
+        sh.info(format!("{} sources removed", removed.len()))?;
+        return sh.json(&json!({ "removed": removed }));
     };
 
-    let source = Source::new(absolute_dir);
-    if !config.has_source(&source.id) {
-        return Ok(());
-    }
+    // deleted dirs can be removed too, they are only made absolute.
+    let Some(absolute_dir) = Workspace::init_from_dir(dir).absolute_dir().ok() else {
+        bail!(CliError::new(ErrorCode::NotFound, "Not an valid directory"));
+    };
+    let removed = remove_source(&mut config, &absolute_dir)?;
+    shell()?.json(&json!({ "removed": removed }))
+}
 
-    config.sources.retain(|s| s.id != source.id);
-    config.flush()?;
+/// Remove source of path from config, and it's git worktree.
+fn remove_source(config: &mut Config, path: &Path) -> Result<Option<SourceOutput>> {
+    let removed = config.update(|config| {
+        let index = config.sources.iter().position(|s| s.path == path);
+        Ok(index.map(|index| config.sources.remove(index)))
+    })?;
+    let Some(source) = removed else {
+        return Ok(None);
+    };
+    let output = SourceOutput::from(&source);
 
-    Ok(())
+    if let Some(git) = source.git {
+        git.remove_worktree(&Config::git_dir(), &source.path)?;
+    }
+
+    Ok(Some(output))
 }
 
 fn cmd_handler_source_list() -> Result<()> {
     let config = appConfig.lock().unwrap();
+    let mut sh = shell()?;
 
     for source in config.sources.iter() {
-        shell()?.info(format!("{}: {}", source.id, source.path.display()))?;
+        match source.git {
+            Some(ref git) => sh.info(format!(
+                "{}: {} ({})",
+                source.id,
+                source.path.display(),
+                git
+            ))?,
+            None => sh.info(format!("{}: {}", source.id, source.path.display()))?,
+        }
+    }
+
+    sh.json(
+        &config
+            .sources
+            .iter()
+            .map(SourceOutput::from)
+            .collect::<Vec<SourceOutput>>(),
+    )
+}
+
+fn cmd_handler_source_discover(cli: &Cli, dir: &PathBuf, depth: usize) -> Result<()> {
+    if !dir.is_dir() {
+        bail!(CliError::new(ErrorCode::NotFound, "Not an valid directory"));
     }
 
+    let mut config = appConfig.lock().unwrap();
+    let pick_config = pick_config(cli, &config, &cli.target())?;
+    let candidates = discover_source_candidates(dir, depth)?
+        .into_iter()
+        .filter(|c| !config.has_source(&Source::new(&c.dir).id))
+        .collect::<Vec<SourceCandidate>>();
+
+    if candidates.is_empty() {
+        let mut sh = shell()?;
+        sh.warn("no new sources found")?;
+        return sh.json(&json!({ "added": [] }));
+    }
+
+    let picked = pick_items(candidates.as_slice(), Some(pick_config))?;
+    let added = config.update(|config| {
+        let mut added = Vec::new();
+        for candidate in picked.iter() {
+            let source = Source::new(&candidate.dir);
+            if !config.has_source(&source.id) {
+                added.push(SourceOutput::from(&source));
+                config.sources.push(source);
+            }
+        }
+        Ok(added)
+    })?;
+
+    let mut sh = shell()?;
+    sh.info(format!("{} sources added", added.len()))?;
+    sh.json(&json!({ "added": added }))
+}
+
+fn cmd_handler_source_check(cli: &Cli) -> Result<()> {
+    let config = appConfig.lock().unwrap();
+    let target = cli.target();
+    let mut sh = shell()?;
+    let mut problems = Vec::new();
+    let mut problem = |sh: &mut Shell, message: String| {
+        problems.push(ErrorOutput {
+            code: ErrorCode::SourceUnavailable,
+            message: message.clone(),
+        });
+        sh.warn(message)
+    };
+
+    let mut available_ids = Vec::<&String>::new();
+    for source in config.sources.iter() {
+        match source.health() {
+            SourceHealth::Ok => available_ids.push(&source.id),
+            health => {
+                problem(
+                    &mut sh,
+                    format!("source {}: {}", source.path.display(), health),
+                )?;
+            }
+        }
+    }
+
+    let lockfile_path = target.lockfile_path()?;
+    for pkg in target.lockfile()?.packages_iter() {
+        if available_ids.contains(&&pkg.source_id) {
+            continue;
+        }
+        let reason = if config.has_source(&pkg.source_id) {
+            "unavailable"
+        } else {
+            "unknown"
+        };
+        problem(
+            &mut sh,
+            format!(
+                "package {} in {} references {} source {}",
+                pkg.name,
+                lockfile_path.display(),
+                reason,
+                pkg.source_id
+            ),
+        )?;
+    }
+
+    let count = problems.len();
+    sh.json(&CheckOutput {
+        ok: count == 0,
+        problems,
+    })?;
+    if count > 0 {
+        bail!(CliError::new(
+            ErrorCode::CheckFailed,
+            format!("{} problems found", count)
+        ));
+    }
+
+    sh.info("all sources are ok")
+}
+
+fn cmd_handler_source_move(cli: &Cli, old: &PathBuf, new: &PathBuf) -> Result<()> {
+    let old = resolve_dir(cli, old);
+    let new = resolve_dir(cli, new);
+
+    let (old_source, new_source) = appConfig.lock().unwrap().update(|config| {
+        let Some(index) = config.sources.iter().position(|s| s.path == old) else {
+            bail!(CliError::new(
+                ErrorCode::NotFound,
+                format!("Source {} does not exist", old.display())
+            ));
+        };
+        // keep kind and git of the source, only the path changes.
+        let new_source = config.sources[index].moved_to(&new);
+        let health = new_source.health();
+        if health != SourceHealth::Ok {
+            bail!(CliError::new(
+                ErrorCode::InvalidArgument,
+                format!("{} can't be the source: {}", new.display(), health)
+            ));
+        }
+        if config.has_source(&new_source.id) {
+            bail!(CliError::new(
+                ErrorCode::AlreadyExists,
+                "Source already exists"
+            ));
+        }
+        let old_source = std::mem::replace(&mut config.sources[index], new_source.clone());
+        Ok((old_source, new_source))
+    })?;
+
+    // lockfiles of all known targets point to the old path.
+    let mut dirs = TargetRegistry::load(Config::targets_file())?
+        .existing_targets()
+        .cloned()
+        .collect::<BTreeSet<PathBuf>>();
+    dirs.insert(cli.target().workspace.absolute_dir()?);
+    let mut updated = 0;
+    let mut targets = Vec::new();
+    for dir in dirs {
+        let relocated = Target::init_from_dir(&dir)
+            .update_lockfile(|lockfile| lockfile.relocate_source(&old_source, &new_source));
+        match relocated {
+            Ok(0) => {}
+            Ok(count) => {
+                updated += count;
+                targets.push(dir);
+            }
+            Err(e) => shell()?.warn(format!(
+                "lockfile of {} is not updated: {:#}",
+                dir.display(),
+                e
+            ))?,
+        }
+    }
+
+    let mut sh = shell()?;
+    sh.info(format!(
+        "source moved to {}, {} locked packages of {} targets updated",
+        new_source.path.display(),
+        updated,
+        targets.len()
+    ))?;
+    sh.json(&json!({
+        "source": SourceOutput::from(&new_source),
+        "relocated_packages": updated,
+        "relocated_targets": targets,
+    }))
+}
+
+/// Packages picked for the `package` command, `selected` ones pre-selected,
+/// none if the picker is cancelled. Packages picked often are listed first,
+/// with `last` the packages picked the last time in target are picked again
+/// without the picker.
+fn pick_packages(
+    cli: &Cli,
+    config: &Config,
+    target: &Target,
+    command: &str,
+    mut pkgs: Vec<Package>,
+    selected: &[Package],
+    last: bool,
+) -> Result<Option<Vec<Package>>> {
+    let dir = target.workspace.absolute_dir()?;
+    let history_file = Config::pick_history_file();
+    let history = PickHistory::load(&history_file)?;
+
+    if last {
+        let Some(names) = history.last(&dir, command) else {
+            bail!(CliError::new(
+                ErrorCode::NotFound,
+                format!("no packages were picked by `package {}` here yet", command)
+            ));
+        };
+        let mut picked = Vec::new();
+        for name in names {
+            match pkgs.iter().find(|p| &p.name == name) {
+                Some(pkg) => picked.push(pkg.clone()),
+                None => shell()?.warn(format!(
+                    "`{}` of the last pick is skipped, it's not in the packages to pick",
+                    name
+                ))?,
+            }
+        }
+        // the history is kept as is, so it can be applied again.
+        return Ok(Some(picked));
+    }
+
+    history.rank(&dir, &mut pkgs, |p| &p.name);
+    let preselected = pkgs
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| selected.contains(p))
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+    let items = package_items(pkgs, config, target)?;
+    let Some(picked) = pick_items_with_selected(
+        &items,
+        &preselected,
+        Some(pick_config(cli, config, target)?),
+    )?
+    else {
+        return Ok(None);
+    };
+    let picked = picked
+        .into_iter()
+        .map(|pkg| Rc::unwrap_or_clone(pkg.raw.inner))
+        .collect::<Vec<Package>>();
+
+    let names = picked
+        .iter()
+        .map(|p| p.name.clone())
+        .collect::<Vec<String>>();
+    // removed packages and the pre-selected ones kept by `edit` are not
+    // ranked higher, only newly picked packages are.
+    let ranked = if command == "remove" {
+        Vec::new()
+    } else {
+        picked
+            .iter()
+            .filter(|p| !selected.contains(p))
+            .map(|p| p.name.clone())
+            .collect()
+    };
+    PickHistory::record(history_file, &dir, command, &names, &ranked)?;
+    Ok(Some(picked))
+}
+
+/// Picker items of packages with aligned columns, grouped by source if
+/// `group_by_source` is set.
+fn package_items<'a>(
+    pkgs: Vec<Package>,
+    config: &'a Config,
+    target: &Target,
+) -> Result<Vec<PackageItemDisplay<'a>>> {
+    let resolved = ResolvedConfig::load(config, &target.workspace.dir)?;
+    Ok(PackageItemDisplay::list(
+        package_formatters(pkgs, config, target),
+        resolved.group_by_source,
+    ))
+}
+
+/// Formatters of packages with their status in target.
+pub(super) fn package_formatters<'a>(
+    mut pkgs: Vec<Package>,
+    config: &'a Config,
+    target: &Target,
+) -> Vec<PackageItemFormatter<'a>> {
+    discover_meta(&mut pkgs);
+    pkgs.into_iter()
+        .map(|pkg| {
+            let status = if is_linked(target, &pkg.name) {
+                PackageStatus::Linked
+            } else if is_added(target, &pkg.name) {
+                PackageStatus::Added
+            } else {
+                PackageStatus::Available
+            };
+            let source = config.sources.iter().find(|s| s.id == pkg.source_id);
+            PackageItemFormatter::new(Rc::new(pkg), source).with_status(status)
+        })
+        .collect()
+}
+
+/// Picker options resolved for current workspace.
+/// `--select` reads the items to pick instead of the picker, `--picker`
+/// overrides the configured picker.
+fn pick_config(cli: &Cli, config: &Config, target: &Target) -> Result<PickConfig> {
+    let resolved = ResolvedConfig::load(config, &target.workspace.dir)?;
+    let mode = match cli.select {
+        Some(_) => PickerMode::Input,
+        None => cli.picker.unwrap_or(resolved.picker),
+    };
+    Ok(PickConfig {
+        mode,
+        fzf: Some(resolved.fzf),
+        inquire: Some(resolved.inquire),
+        builtin: Some(resolved.builtin),
+        input: Some(InputPickerConfig {
+            path: cli.select.as_ref().map(|path| {
+                if path.as_os_str() == "-" {
+                    path.clone()
+                } else {
+                    resolve_dir(cli, path)
+                }
+            }),
+        }),
+        preview: preview_command(target),
+    })
+}
+
+/// `npk __preview` of the same config file and workspace.
+fn preview_command(target: &Target) -> Option<PreviewCommand> {
+    Some(PreviewCommand {
+        program: std::env::current_exe().ok()?,
+        args: vec![
+            "--cwd".to_owned(),
+            target.workspace.dir.to_string_lossy().into_owned(),
+            "--config".to_owned(),
+            Config::root_config_path().to_string_lossy().into_owned(),
+            "__preview".to_owned(),
+        ],
+    })
+}
+
+fn cmd_handler_preview(cli: &Cli, id: &str) -> Result<()> {
+    let config = appConfig.lock().unwrap();
+    let target = cli.target();
+    let pkg = find_package(&config, &target, id)?;
+    let source = config.sources.iter().find(|s| s.id == pkg.source_id);
+
+    shell()?.print(package_preview(&pkg, source, &target).trim_end())?;
     Ok(())
 }
 
+/// Absolute path of dir relative to cli cwd, the dir may not exist.
+fn resolve_dir(cli: &Cli, dir: &PathBuf) -> PathBuf {
+    let dir = cli.cwd.as_ref().unwrap().join(dir);
+    dir.canonicalize().unwrap_or(dir)
+}
+
 /// handle packages, like list packages from all sources.
 fn cmd_handler_package_sub_cli(cli: &Cli, command: &PackageSubCli) -> Result<()> {
     match command {
-        PackageSubCli::Add => {
-            cmd_handler_package_add(cli)?;
+        PackageSubCli::Add(args) => {
+            cmd_handler_package_add(cli, args)?;
+        }
+        PackageSubCli::Remove(args) => {
+            cmd_handler_package_remove(cli, args)?;
         }
-        PackageSubCli::Remove => {
-            cmd_handler_package_remove(cli)?;
+        PackageSubCli::Edit { source, last } => {
+            cmd_handler_package_edit(cli, source.as_deref(), *last)?;
         }
         PackageSubCli::List => {
             cmd_handler_package_list_all(cli)?;
@@ -269,104 +1505,233 @@ fn cmd_handler_package_sub_cli(cli: &Cli, command: &PackageSubCli) -> Result<()>
     Ok(())
 }
 
-fn cmd_handler_package_list_all(_cli: &Cli) -> Result<()> {
+fn cmd_handler_package_list_all(cli: &Cli) -> Result<()> {
     let config = appConfig.lock().unwrap();
-    let mut pkgs = config.sources.iter().flat_map(packages_from_source);
+    let pkgs = config
+        .sources
+        .iter()
+        .flat_map(packages_from_source)
+        .collect::<Vec<Package>>();
 
     let mut sh = shell()?;
 
-    if pkgs.by_ref().peekable().peek().is_none() {
+    if pkgs.is_empty() {
         sh.warn("no packages to list")?;
     }
 
-    for pkg in pkgs {
-        sh.info(format!("{:?}", pkg.name))?;
+    for pkg in pkgs.iter() {
+        sh.info(&pkg.name)?;
     }
 
-    Ok(())
+    sh.json(&package_outputs(&pkgs, &config, &cli.target()))
 }
 
 /// https://github.com/mikaelmello/inquire/blob/main/inquire/examples/multiselect.rs
 /// Change the workspace's packages.
-fn cmd_handler_package_add(cli: &Cli) -> Result<()> {
+fn cmd_handler_package_add(cli: &Cli, args: &PackageArgs) -> Result<()> {
     let config = appConfig.lock().unwrap();
     let target = cli.target();
-    let pkgs = config
+    let mut pkgs = config
         .sources
         .iter()
         .flat_map(packages_from_source)
         .collect::<Vec<Package>>();
-    let lockfile_pkgs = {
-        let lockfile = target.lockfile()?;
-        lockfile.packages_iter().collect::<Vec<Package>>()
-    };
+    if let Some(ref source) = args.source {
+        let source = find_source(cli, &config, source)?;
+        pkgs.retain(|p| p.source_id == source.id);
+    }
 
-    let get_weak_source =
-        |source_id: &String| config.sources.iter().find(|s| &s.id == source_id).unwrap();
+    let picked = if args.names.is_empty() {
+        let lockfile_pkgs = {
+            let lockfile = target.lockfile()?;
+            lockfile.packages_iter().collect::<Vec<Package>>()
+        };
 
-    let pkgs_to_pick = difference_packages(&pkgs, &lockfile_pkgs)
-        .into_iter()
-        .map(Rc::new);
-
-    let picked = pick_items(
-        pkgs_to_pick
-            .map(|p| {
-                let weak_source = get_weak_source(&p.source_id);
-                PackageItemDisplay::new(PackageItemFormatter::new(Rc::clone(&p), weak_source))
-            })
-            .collect::<Vec<PackageItemDisplay>>()
-            .as_slice(),
-        Some(Default::default()),
-    )?;
+        let pkgs_to_pick = difference_packages(&pkgs, &lockfile_pkgs);
 
-    {
-        let mut lockfile = target.lockfile_mut()?;
+        pick_packages(cli, &config, &target, "add", pkgs_to_pick, &[], args.last)?
+            .unwrap_or_default()
+    } else {
+        let matched = match_packages_of_args(pkgs, args, "add")?;
+        let mut sources_of_name = HashMap::<&str, usize>::new();
+        for pkg in matched.iter() {
+            *sources_of_name.entry(&pkg.name).or_default() += 1;
+        }
+        if let Some((name, _)) = sources_of_name.iter().find(|(_, count)| **count > 1) {
+            bail!(CliError::new(
+                ErrorCode::Ambiguous,
+                format!(
+                    "`{}` is in multiple sources, use --source to choose one",
+                    name
+                )
+            ));
+        }
+        matched
+    };
 
+    TargetRegistry::register(Config::targets_file(), target.workspace.absolute_dir()?)?;
+    target.update_lockfile(|lockfile| {
         for pkg in picked.iter().cloned() {
-            let raw = pkg.raw;
-            lockfile.add_package(raw.inner.name.clone(), Rc::unwrap_or_clone(raw.inner));
+            lockfile.add_package(pkg.name.clone(), pkg);
         }
-    }
-    target.flush_lockfile()?;
+    })?;
 
-    shell()?.info(format!("{} packages added", picked.len()))?;
-    Ok(())
+    let mut sh = shell()?;
+    sh.info(format!("{} packages added", picked.len()))?;
+    sh.json(&json!({ "added": package_outputs(&picked, &config, &target) }))
 }
 
-// TODO: package and source existence check
-fn cmd_handler_package_remove(cli: &Cli) -> Result<()> {
+fn cmd_handler_package_remove(cli: &Cli, args: &PackageArgs) -> Result<()> {
     let config = appConfig.lock().unwrap();
     let target = cli.target();
 
-    let lockfile_pkgs = {
+    let mut lockfile_pkgs = {
         let lockfile = target.lockfile()?;
         lockfile.packages_iter().collect::<Vec<Package>>()
     };
+    if let Some(ref source) = args.source {
+        let source = find_source(cli, &config, source)?;
+        lockfile_pkgs.retain(|p| p.source_id == source.id);
+    }
 
-    let get_weak_source =
-        |source_id: &String| config.sources.iter().find(|s| &s.id == source_id).unwrap();
+    let picked = if args.names.is_empty() {
+        pick_packages(
+            cli,
+            &config,
+            &target,
+            "remove",
+            lockfile_pkgs,
+            &[],
+            args.last,
+        )?
+        .unwrap_or_default()
+    } else {
+        match_packages_of_args(lockfile_pkgs, args, "remove")?
+    };
 
-    let pkgs_to_pick = lockfile_pkgs
-        .into_iter()
-        .map(Rc::new)
-        .map(|p| {
-            PackageItemDisplay::new(PackageItemFormatter::new(
-                Rc::clone(&p),
-                get_weak_source(&p.source_id),
-            ))
-        })
-        .collect::<Vec<PackageItemDisplay>>();
+    target.update_lockfile(|lockfile| {
+        for pkg in picked.iter() {
+            lockfile.remove_package(pkg.name.clone());
+        }
+    })?;
+
+    let mut sh = shell()?;
+    sh.info(format!("{} packages removed", picked.len()))?;
+    sh.json(&json!({ "removed": package_outputs(&picked, &config, &target) }))
+}
+
+fn cmd_handler_package_edit(cli: &Cli, source: Option<&str>, last: bool) -> Result<()> {
+    let config = appConfig.lock().unwrap();
+    let target = cli.target();
+
+    let mut locked = target.lockfile()?.packages_iter().collect::<Vec<Package>>();
+    let mut pkgs = config
+        .sources
+        .iter()
+        .flat_map(packages_from_source)
+        .collect::<Vec<Package>>();
+    if let Some(source) = source {
+        let source = find_source(cli, &config, source)?;
+        pkgs.retain(|p| p.source_id == source.id);
+        locked.retain(|p| p.source_id == source.id);
+    }
+    // locked packages of removed sources can be deselected too.
+    pkgs.extend(difference_packages(&locked, &pkgs));
+
+    let Some(picked) = pick_packages(cli, &config, &target, "edit", pkgs, &locked, last)? else {
+        let mut sh = shell()?;
+        sh.info("cancelled, no packages changed")?;
+        return sh.json(&json!({ "added": [], "removed": [] }));
+    };
 
-    let picked = pick_items(pkgs_to_pick.as_slice(), Some(Default::default()))?;
+    let added = difference_packages(&picked, &locked);
+    let removed = difference_packages(&locked, &picked);
+    if let Some(name) = added
+        .iter()
+        .enumerate()
+        .find(|(i, p)| added[..*i].iter().any(|other| other.name == p.name))
+        .map(|(_, p)| &p.name)
     {
-        let mut lockfile = target.lockfile_mut()?;
+        bail!(CliError::new(
+            ErrorCode::Ambiguous,
+            format!("`{}` is selected from multiple sources, select one", name)
+        ));
+    }
 
-        for pkg in picked.iter().cloned() {
-            lockfile.remove_package(pkg.raw.inner.name.clone());
+    TargetRegistry::register(Config::targets_file(), target.workspace.absolute_dir()?)?;
+    target.update_lockfile(|lockfile| {
+        // remove first, the same name may be added from another source.
+        for pkg in removed.iter() {
+            lockfile.remove_package(pkg.name.clone());
         }
+        for pkg in added.iter().cloned() {
+            lockfile.add_package(pkg.name.clone(), pkg);
+        }
+    })?;
+
+    let mut sh = shell()?;
+    sh.info(format!(
+        "{} packages added, {} packages removed",
+        added.len(),
+        removed.len()
+    ))?;
+    sh.json(&json!({
+        "added": package_outputs(&added, &config, &target),
+        "removed": package_outputs(&removed, &config, &target),
+    }))
+}
+
+fn package_outputs(pkgs: &[Package], config: &Config, target: &Target) -> Vec<PackageOutput> {
+    let mut pkgs = pkgs.to_vec();
+    discover_meta(&mut pkgs);
+    pkgs.iter()
+        .map(|pkg| PackageOutput::new(pkg, config, target))
+        .collect()
+}
+
+/// Packages matching names of args, packages matched by glob patterns must
+/// be confirmed unless `--yes` is given.
+fn match_packages_of_args(
+    pkgs: Vec<Package>,
+    args: &PackageArgs,
+    action: &str,
+) -> Result<Vec<Package>> {
+    let matched = match_packages(pkgs, &args.names)
+        .map_err(|e| CliError::new(ErrorCode::NotFound, format!("{:#}", e)))?;
+    if args.yes || !args.names.iter().any(|n| is_glob_pattern(n)) {
+        return Ok(matched);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!(CliError::new(
+            ErrorCode::InteractiveRequired,
+            format!("pass --yes to {} packages matched by glob patterns", action)
+        ));
     }
-    target.flush_lockfile()?;
 
-    shell()?.info(format!("{} packages removed", picked.len()))?;
-    Ok(())
+    {
+        let mut sh = shell()?;
+        for pkg in matched.iter() {
+            sh.info(&pkg.name)?;
+        }
+    }
+    if !confirm(&format!("{} {} packages?", action, matched.len()))? {
+        return Ok(Vec::new());
+    }
+    Ok(matched)
+}
+
+/// Source by id or path.
+fn find_source<'a>(cli: &Cli, config: &'a Config, source: &str) -> Result<&'a Source> {
+    let path = resolve_dir(cli, &PathBuf::from(source));
+    config
+        .sources
+        .iter()
+        .find(|s| s.id == source || s.path == path)
+        .ok_or_else(|| {
+            CliError::new(
+                ErrorCode::NotFound,
+                format!("Source {} does not exist", source),
+            )
+            .into()
+        })
 }
@@ -0,0 +1,129 @@
+//! Dynamic completion of values read from config and lockfile, shells call
+//! `npk` with `COMPLETE=<shell>` to get them.
+//! Completers run before `--config` and `--cwd` are parsed, so they use the
+//! default config file and current dir, errors are ignored.
+
+use crate::config::Config;
+use clap_complete::engine::CompletionCandidate;
+use npmpink_core::ops::packages::packages_from_source;
+use npmpink_core::target::Target;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Write;
+
+/// Env var that shells set to request completions.
+pub(crate) const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Names of packages in the sources, for `package add`.
+pub(crate) fn complete_source_packages(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    let pkgs = config.sources.iter().flat_map(|source| {
+        packages_from_source(source)
+            .into_iter()
+            .map(|pkg| (pkg.name, source.path.display().to_string()))
+    });
+    candidates(current, pkgs)
+}
+
+/// Names of packages in the lockfile of current workspace, for `package remove`.
+pub(crate) fn complete_locked_packages(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(dir) = std::env::current_dir() else {
+        return Vec::new();
+    };
+    let target = Target::init_from_dir(dir);
+    let Ok(lockfile) = target.lockfile() else {
+        return Vec::new();
+    };
+    candidates(
+        current,
+        lockfile.packages_iter().map(|pkg| (pkg.name, pkg.dir)),
+    )
+}
+
+/// Paths of sources, for `source remove` and `source move`.
+pub(crate) fn complete_source_paths(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    candidates(
+        current,
+        config
+            .sources
+            .iter()
+            .map(|source| (source.path.display().to_string(), source.id.clone())),
+    )
+}
+
+/// Paths and ids of sources, for `--source`.
+pub(crate) fn complete_source_ids(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(config) = Config::load() else {
+        return Vec::new();
+    };
+    candidates(
+        current,
+        config.sources.iter().flat_map(|source| {
+            let path = source.path.display().to_string();
+            [(path.clone(), source.id.clone()), (source.id.clone(), path)]
+        }),
+    )
+}
+
+/// Candidates starting with current value, sorted and deduplicated by value.
+fn candidates(
+    current: &OsStr,
+    values: impl Iterator<Item = (String, String)>,
+) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    values
+        .filter(|(value, _)| value.starts_with(current.as_ref()))
+        .collect::<BTreeMap<String, String>>()
+        .into_iter()
+        .map(|(value, help)| CompletionCandidate::new(value).help(Some(help.into())))
+        .collect()
+}
+
+/// Shells that registration scripts can be printed for.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub(crate) enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Print the script that registers `npk` completions in the shell.
+pub(crate) fn write_registration(
+    shell: CompletionShell,
+    buf: &mut dyn Write,
+) -> Result<(), std::io::Error> {
+    use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &Bash,
+        CompletionShell::Zsh => &Zsh,
+        CompletionShell::Fish => &Fish,
+    };
+    let exe = std::env::current_exe()?;
+    completer.write_registration(COMPLETE_VAR, "npk", "npk", &exe.to_string_lossy(), buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let values = [
+            ("@acme/ui".to_string(), "/libs".to_string()),
+            ("@acme/utils".to_string(), "/libs".to_string()),
+            ("@acme/ui".to_string(), "/other".to_string()),
+            ("lodash".to_string(), "/vendor".to_string()),
+        ];
+        let values = candidates(OsStr::new("@acme/"), values.into_iter())
+            .into_iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["@acme/ui", "@acme/utils"]);
+    }
+}
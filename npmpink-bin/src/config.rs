@@ -1,24 +1,33 @@
-use anyhow::Result;
+use crate::dirs::{config_file_override, NpkDirs};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 #[allow(unused_imports)]
 use home::home_dir as crate_home_dir;
 use lazy_static::lazy_static;
+use npmpink_core::file_lock::{write_atomic, FileLock};
 use npmpink_core::source::Source;
+use npmpink_tui::select::{BuiltinPickerConfig, FzfPickerConfig, InquirePickerConfig, PickerMode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use thiserror::Error;
 
 lazy_static! {
-    static ref ROOT_CONFIG_PATH: PathBuf = get_root_config_path();
+    static ref DIRS: NpkDirs = get_dirs();
     pub static ref appConfig: Mutex<Config> = Mutex::new(Config::load_or_default());
 }
 
-fn get_root_config_path() -> PathBuf {
-    home_dir()
-        .unwrap_or_else(|| panic!("Can not get the home dir"))
-        .join(".npmpink.json")
+fn get_dirs() -> NpkDirs {
+    NpkDirs::resolve(
+        &home_dir().unwrap_or_else(|| panic!("Can not get the home dir")),
+        env_var,
+        config_file_override().map(PathBuf::as_path),
+    )
 }
 
 #[cfg(not(test))]
@@ -30,79 +39,223 @@ fn home_dir() -> Option<PathBuf> {
     Some(PathBuf::from(env!("CARGO_WORKSPACE_DIR")))
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[cfg(not(test))]
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok()
+}
+#[cfg(test)]
+fn env_var(_key: &str) -> Option<String> {
+    None
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum Mode {
     Symlink,
     Copy,
 }
 
+/// Package manager used to link packages in symlink mode.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageManager {
+    #[default]
+    Pnpm,
+    Npm,
+    Yarn,
+}
+
+impl PackageManager {
+    pub fn program(&self) -> &'static str {
+        match self {
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     pub mode: Mode,
+    #[serde(default)]
+    pub package_manager: PackageManager,
+    #[serde(default)]
+    pub picker: PickerMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fzf: Option<FzfPickerConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inquire: Option<InquirePickerConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<BuiltinPickerConfig>,
+    /// List packages under headers of their sources in pickers.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub group_by_source: bool,
     pub sources: Vec<Source>,
 }
 
 // maybe move this to config_health.rs module.
 /// Health check for config
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Error)]
 pub enum HealthCheckError {
     #[error("Config file does not exist")]
     ConfigFileNotExist,
-    #[error("Config file is invalid")]
-    ConfigFileInvalid,
+    #[error("Config file {} can not be read: {message}", .path.display())]
+    ConfigFileUnreadable { path: PathBuf, message: String },
+    #[error(
+        "Config file {} is invalid at line {line} column {column}, field `{field}`: {message}",
+        .path.display()
+    )]
+    ConfigFileInvalid {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        /// Path of the field, like `sources[0].path`.
+        field: String,
+        message: String,
+    },
 }
 
 impl Config {
     pub fn root_config_path() -> &'static PathBuf {
-        &ROOT_CONFIG_PATH
+        &DIRS.config_file
+    }
+
+    pub fn data_dir() -> &'static PathBuf {
+        &DIRS.data_dir
+    }
+
+    pub fn cache_dir() -> &'static PathBuf {
+        &DIRS.cache_dir
+    }
+
+    /// Registry of targets npmpink is used in.
+    pub fn targets_file() -> PathBuf {
+        Self::data_dir().join("targets.json")
+    }
+
+    /// Packages picked in pickers.
+    pub fn pick_history_file() -> PathBuf {
+        Self::data_dir().join("history.json")
+    }
+
+    /// Where git sources are checked out.
+    pub fn git_dir() -> PathBuf {
+        Self::data_dir().join("git")
+    }
+
+    /// Move `~/.npmpink.json` to the config file at default place if it does
+    /// not exist yet, returns the legacy path if migrated.
+    pub fn migrate_legacy() -> Result<Option<PathBuf>> {
+        if !DIRS.is_default_config_file || DIRS.config_file.try_exists()? {
+            return Ok(None);
+        }
+        let Some(home) = home_dir() else {
+            return Ok(None);
+        };
+        let legacy = NpkDirs::legacy_config_file(&home);
+        if !legacy.try_exists()? {
+            return Ok(None);
+        }
+
+        if let Some(parent) = DIRS.config_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&legacy, &DIRS.config_file)
+            .with_context(|| format!("failed to migrate {}", legacy.display()))?;
+        fs::remove_file(&legacy)?;
+
+        Ok(Some(legacy))
     }
 
     /// Do healthy check for root config
     pub fn healthcheck() -> Result<PathBuf, HealthCheckError> {
-        use std::path;
-
         let config_path = Config::root_config_path();
+        Self::load()?;
+
+        Ok(config_path.clone())
+    }
 
-        // how to make this error handle better
-        if !path::Path::new(config_path).try_exists().unwrap() {
+    /// Load and validate the root config.
+    pub fn load() -> Result<Self, HealthCheckError> {
+        let config_path = Self::root_config_path();
+        let unreadable = |e: std::io::Error| HealthCheckError::ConfigFileUnreadable {
+            path: config_path.clone(),
+            message: e.to_string(),
+        };
+
+        if !config_path.try_exists().map_err(unreadable)? {
             return Err(HealthCheckError::ConfigFileNotExist);
         }
+        let content = fs::read_to_string(config_path).map_err(unreadable)?;
 
-        Ok(config_path.clone())
+        Self::parse(&content, config_path)
     }
 
-    pub fn create_from_default() -> Result<()> {
-        let root_config_path = Self::root_config_path();
-        let mut file = fs::File::create(root_config_path)?;
-        let content = serde_json::to_string_pretty(&Config::default()).unwrap();
+    pub fn parse(content: &str, config_path: &Path) -> Result<Self, HealthCheckError> {
+        let de = &mut serde_json::Deserializer::from_str(content);
 
-        file.write_all(content.as_bytes())?;
+        serde_path_to_error::deserialize(de).map_err(|e| {
+            let field = e.path().to_string();
+            let inner = e.into_inner();
+            let message = inner.to_string();
+            // the location is reported separately.
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(message.as_str(), |(m, _)| m)
+                .to_owned();
 
-        Ok(())
+            HealthCheckError::ConfigFileInvalid {
+                path: config_path.to_path_buf(),
+                line: inner.line(),
+                column: inner.column(),
+                field,
+                message,
+            }
+        })
     }
 
-    pub(crate) fn load_or_default() -> Self {
+    pub fn create_from_default() -> Result<()> {
         let root_config_path = Self::root_config_path();
+        let _lock = FileLock::acquire(root_config_path)?;
+        let content = serde_json::to_string_pretty(&Config::default()).unwrap();
 
-        if root_config_path.try_exists().unwrap_or(false) {
-            let content = fs::read_to_string(root_config_path);
-            if let Ok(content) = content {
-                return serde_json::from_str(&content).unwrap_or(Config::default());
-            }
-        }
+        write_atomic(root_config_path, content)
+    }
 
-        Config::default()
+    /// Invalid config is loaded as default, but `update` refuses to overwrite it.
+    pub(crate) fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
     }
 
     pub fn has_source(&self, id: &str) -> bool {
         self.sources.iter().any(|s| s.id == id)
     }
 
-    pub fn flush(&self) -> std::io::Result<()> {
-        let root_config_path = Self::root_config_path();
-        let content = serde_json::to_string_pretty(self).unwrap();
-        fs::write(root_config_path, content.as_bytes())
+    /// Like `update_json` of the config file, but an invalid config is never
+    /// overwritten and `self` is replaced by the reloaded config.
+    pub fn update<T>(&mut self, f: impl FnOnce(&mut Config) -> Result<T>) -> Result<T> {
+        let _lock = FileLock::acquire(Self::root_config_path())?;
+        *self = Self::load_for_update()?;
+
+        let before = serde_json::to_string_pretty(self)?;
+        let result = f(self)?;
+        let content = serde_json::to_string_pretty(self)?;
+        if content != before {
+            write_atomic(Self::root_config_path(), content)?;
+        }
+        Ok(result)
+    }
+
+    /// Config to be overwritten, which must be valid or not exist.
+    fn load_for_update() -> Result<Self> {
+        match Self::load() {
+            Ok(config) => Ok(config),
+            Err(HealthCheckError::ConfigFileNotExist) => Ok(Config::default()),
+            Err(e) => Err(e).context("refuse to overwrite the config file, fix it first"),
+        }
     }
 }
 
@@ -110,27 +263,292 @@ impl Default for Config {
     fn default() -> Self {
         Config {
             mode: Mode::Symlink,
+            package_manager: Default::default(),
+            picker: Default::default(),
+            fzf: None,
+            inquire: None,
+            builtin: None,
+            group_by_source: false,
             sources: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct WorkspaceConfig {
-    mode: Mode,
+/// Config of target workspace, from `npmpink.config.json` or the `"npmpink"`
+/// key of package.json. Unset fields fallback to the global config.
+#[derive(Debug, Deserialize, Serialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<Mode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_manager: Option<PackageManager>,
+    /// Dirs relative to the target to sync packages into, for monorepo targets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<String>>,
+    /// Shell commands to run in the target before sync, like building packages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_sync: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picker: Option<PickerMode>,
+    /// Options of fzf, replaces the global ones as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fzf: Option<FzfPickerConfig>,
+    /// Options of inquire, replaces the global ones as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inquire: Option<InquirePickerConfig>,
+    /// Options of the built-in picker, replaces the global ones as a whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<BuiltinPickerConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by_source: Option<bool>,
 }
 
-impl Default for WorkspaceConfig {
-    fn default() -> Self {
-        WorkspaceConfig {
-            mode: Mode::Symlink,
+impl WorkspaceConfig {
+    pub const FILE_NAME: &'static str = "npmpink.config.json";
+
+    /// Load from `npmpink.config.json` under dir.
+    pub fn load_file(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(Self::FILE_NAME);
+        if !path.try_exists()? {
+            return Ok(None);
         }
+        let content = fs::read_to_string(&path)?;
+        Self::parse(&content)
+            .map(Some)
+            .with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Save to `npmpink.config.json` under dir, callers changing existing
+    /// config should hold the `FileLock` of it.
+    pub fn flush(&self, dir: &Path) -> Result<()> {
+        write_atomic(
+            dir.join(Self::FILE_NAME),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let de = &mut serde_json::Deserializer::from_str(content);
+        serde_path_to_error::deserialize(de).map_err(|e| {
+            let field = e.path().to_string();
+            anyhow::anyhow!("invalid field `{}`: {}", field, e.into_inner())
+        })
+    }
+
+    /// Load from the `"npmpink"` key of package.json under dir.
+    pub fn load_package_json(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join("package.json");
+        if !path.try_exists()? {
+            return Ok(None);
+        }
+        let content: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)
+            .with_context(|| format!("invalid {}", path.display()))?;
+        match content.get("npmpink") {
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .with_context(|| format!("invalid \"npmpink\" config in {}", path.display())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Config keys that can be changed with `npk config get/set/unset`.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum ConfigKey {
+    Mode,
+    PackageManager,
+    Picker,
+    Members,
+    PreSync,
+}
+
+impl ConfigKey {
+    /// Name of the key in config files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigKey::Mode => "mode",
+            ConfigKey::PackageManager => "package_manager",
+            ConfigKey::Picker => "picker",
+            ConfigKey::Members => "members",
+            ConfigKey::PreSync => "pre_sync",
+        }
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self, ConfigKey::Members | ConfigKey::PreSync)
+    }
+
+    /// Keys that only make sense for a target, not in the global config.
+    pub fn is_workspace_only(&self) -> bool {
+        self.is_list()
+    }
+
+    /// Json value of the values given in command line.
+    pub fn parse_values(&self, values: &[String]) -> Result<Value> {
+        if self.is_list() {
+            return Ok(Value::from(values.to_vec()));
+        }
+        match values {
+            [value] => Ok(Value::from(value.as_str())),
+            _ => bail!("`{}` takes exactly one value", self.name()),
+        }
+    }
+}
+
+/// Set the key of config document, or remove it if value is None, returns
+/// the document parsed as `T` so invalid values are never saved.
+pub fn update_config_doc<T: DeserializeOwned>(
+    doc: &mut Value,
+    key: ConfigKey,
+    value: Option<Value>,
+) -> Result<T> {
+    let Some(map) = doc.as_object_mut() else {
+        bail!("config must be a json object");
+    };
+    match value {
+        Some(value) => map.insert(key.name().to_owned(), value),
+        None => map.remove(key.name()),
+    };
+
+    serde_json::from_value(doc.clone())
+        .with_context(|| format!("invalid value for `{}`", key.name()))
+}
+
+/// Where a resolved config value comes from.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigOrigin {
+    Default,
+    Global,
+    PackageJson,
+    WorkspaceFile,
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let origin = match self {
+            ConfigOrigin::Default => "default".to_owned(),
+            ConfigOrigin::Global => Config::root_config_path().display().to_string(),
+            ConfigOrigin::PackageJson => "package.json#npmpink".to_owned(),
+            ConfigOrigin::WorkspaceFile => WorkspaceConfig::FILE_NAME.to_owned(),
+        };
+        write!(f, "{}", origin)
+    }
+}
+
+/// Config used to run commands in target.
+///
+/// Precedence from high to low: `npmpink.config.json`, `"npmpink"` key in
+/// package.json, global config, default.
+#[derive(Debug, Serialize)]
+pub struct ResolvedConfig {
+    pub mode: Mode,
+    pub package_manager: PackageManager,
+    pub members: Vec<String>,
+    pub pre_sync: Vec<String>,
+    pub picker: PickerMode,
+    pub fzf: FzfPickerConfig,
+    pub inquire: InquirePickerConfig,
+    pub builtin: BuiltinPickerConfig,
+    pub group_by_source: bool,
+    pub origins: BTreeMap<&'static str, ConfigOrigin>,
+}
+
+impl ResolvedConfig {
+    /// Resolve config of target dir.
+    pub fn load(config: &Config, dir: &Path) -> Result<Self> {
+        let mut layers = Vec::new();
+        if let Some(c) = WorkspaceConfig::load_package_json(dir)? {
+            layers.push((ConfigOrigin::PackageJson, c));
+        }
+        if let Some(c) = WorkspaceConfig::load_file(dir)? {
+            layers.push((ConfigOrigin::WorkspaceFile, c));
+        }
+        Ok(Self::resolve(config, &layers))
+    }
+
+    /// Layers are ordered from low to high precedence.
+    pub fn resolve(config: &Config, layers: &[(ConfigOrigin, WorkspaceConfig)]) -> Self {
+        let mut resolved = ResolvedConfig {
+            mode: config.mode,
+            package_manager: config.package_manager,
+            members: vec![".".to_owned()],
+            pre_sync: Vec::new(),
+            picker: config.picker,
+            fzf: config.fzf.clone().unwrap_or_default(),
+            inquire: config.inquire.clone().unwrap_or_default(),
+            builtin: config.builtin.clone().unwrap_or_default(),
+            group_by_source: config.group_by_source,
+            origins: BTreeMap::from([
+                ("mode", ConfigOrigin::Global),
+                ("package_manager", ConfigOrigin::Global),
+                ("members", ConfigOrigin::Default),
+                ("pre_sync", ConfigOrigin::Default),
+                ("picker", ConfigOrigin::Global),
+                ("fzf", global_or_default(&config.fzf)),
+                ("inquire", global_or_default(&config.inquire)),
+                ("builtin", global_or_default(&config.builtin)),
+                ("group_by_source", ConfigOrigin::Global),
+            ]),
+        };
+
+        for (origin, layer) in layers {
+            if let Some(mode) = layer.mode {
+                resolved.mode = mode;
+                resolved.origins.insert("mode", *origin);
+            }
+            if let Some(package_manager) = layer.package_manager {
+                resolved.package_manager = package_manager;
+                resolved.origins.insert("package_manager", *origin);
+            }
+            if let Some(ref members) = layer.members {
+                resolved.members = members.clone();
+                resolved.origins.insert("members", *origin);
+            }
+            if let Some(ref pre_sync) = layer.pre_sync {
+                resolved.pre_sync = pre_sync.clone();
+                resolved.origins.insert("pre_sync", *origin);
+            }
+            if let Some(picker) = layer.picker {
+                resolved.picker = picker;
+                resolved.origins.insert("picker", *origin);
+            }
+            if let Some(ref fzf) = layer.fzf {
+                resolved.fzf = fzf.clone();
+                resolved.origins.insert("fzf", *origin);
+            }
+            if let Some(ref inquire) = layer.inquire {
+                resolved.inquire = inquire.clone();
+                resolved.origins.insert("inquire", *origin);
+            }
+            if let Some(ref builtin) = layer.builtin {
+                resolved.builtin = builtin.clone();
+                resolved.origins.insert("builtin", *origin);
+            }
+            if let Some(group_by_source) = layer.group_by_source {
+                resolved.group_by_source = group_by_source;
+                resolved.origins.insert("group_by_source", *origin);
+            }
+        }
+
+        resolved
+    }
+}
+
+fn global_or_default<T>(value: &Option<T>) -> ConfigOrigin {
+    match value {
+        Some(_) => ConfigOrigin::Global,
+        None => ConfigOrigin::Default,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use npmpink_tui::color::Color;
+    use npmpink_tui::select::BuiltinMatcher;
     use std::path::{Path, PathBuf};
 
     #[test]
@@ -145,6 +563,106 @@ mod tests {
         let _ = WorkspaceConfig::default();
     }
 
+    #[test]
+    fn test_workspace_config_from_package_json() {
+        let dir = PathBuf::from(env!("CARGO_WORKSPACE_DIR")).join("assets_/dummy");
+        assert_eq!(WorkspaceConfig::load_package_json(&dir).unwrap(), None);
+        assert_eq!(WorkspaceConfig::load_file(&dir).unwrap(), None);
+    }
+
+    #[test]
+    fn test_resolve_config_precedence() {
+        let config = Config {
+            package_manager: PackageManager::Npm,
+            ..Default::default()
+        };
+        let package_json: WorkspaceConfig =
+            serde_json::from_str(r#"{"mode": "copy", "members": ["apps/web"]}"#).unwrap();
+        let file: WorkspaceConfig = serde_json::from_str(r#"{"mode": "symlink"}"#).unwrap();
+
+        let resolved = ResolvedConfig::resolve(
+            &config,
+            &[
+                (ConfigOrigin::PackageJson, package_json),
+                (ConfigOrigin::WorkspaceFile, file),
+            ],
+        );
+
+        assert_eq!(resolved.mode, Mode::Symlink);
+        assert_eq!(resolved.origins["mode"], ConfigOrigin::WorkspaceFile);
+        assert_eq!(resolved.members, vec!["apps/web"]);
+        assert_eq!(resolved.origins["members"], ConfigOrigin::PackageJson);
+        assert_eq!(resolved.package_manager, PackageManager::Npm);
+        assert_eq!(resolved.origins["package_manager"], ConfigOrigin::Global);
+        assert_eq!(resolved.origins["pre_sync"], ConfigOrigin::Default);
+        assert_eq!(resolved.origins["fzf"], ConfigOrigin::Default);
+    }
+
+    #[test]
+    fn test_resolve_picker_options() {
+        let config: Config = serde_json::from_str(
+            r#"{
+                "mode": "symlink",
+                "sources": [],
+                "fzf": {"height": "40%", "layout": "reverse", "bindings": ["ctrl-a:select-all"]},
+                "inquire": {"page_size": 12, "colors": {"selected": "pink"}},
+                "builtin": {"matcher": "skim"}
+            }"#,
+        )
+        .unwrap();
+        let file: WorkspaceConfig =
+            serde_json::from_str(r#"{"fzf": {"args": ["--cycle"]}, "group_by_source": true}"#)
+                .unwrap();
+
+        let resolved = ResolvedConfig::resolve(&config, &[(ConfigOrigin::WorkspaceFile, file)]);
+        assert_eq!(resolved.fzf.height, None);
+        assert_eq!(resolved.fzf.args, vec!["--cycle"]);
+        assert_eq!(resolved.origins["fzf"], ConfigOrigin::WorkspaceFile);
+        assert_eq!(resolved.inquire.page_size, Some(12));
+        assert_eq!(resolved.inquire.colors.selected, Some(Color::Pink));
+        assert_eq!(resolved.origins["inquire"], ConfigOrigin::Global);
+        assert_eq!(resolved.builtin.matcher, BuiltinMatcher::Skim);
+        assert!(resolved.group_by_source);
+        assert_eq!(
+            resolved.origins["group_by_source"],
+            ConfigOrigin::WorkspaceFile
+        );
+
+        assert!(serde_json::from_str::<WorkspaceConfig>(r#"{"fzf": {"heigth": "40%"}}"#).is_err());
+        // unset options are not written back to the global config.
+        let json = serde_json::to_string(&Config::default()).unwrap();
+        assert!(!json.contains(r#""fzf":"#) && !json.contains(r#""inquire":"#));
+        assert!(!json.contains("group_by_source"));
+    }
+
+    #[test]
+    fn test_update_config_doc() {
+        let mut doc = serde_json::to_value(Config::default()).unwrap();
+        let value = ConfigKey::Mode.parse_values(&["copy".into()]).unwrap();
+        let config: Config = update_config_doc(&mut doc, ConfigKey::Mode, Some(value)).unwrap();
+        assert_eq!(config.mode, Mode::Copy);
+
+        let value = ConfigKey::Mode.parse_values(&["hardlink".into()]).unwrap();
+        assert!(update_config_doc::<Config>(&mut doc, ConfigKey::Mode, Some(value)).is_err());
+        assert!(ConfigKey::Picker.parse_values(&[]).is_err());
+
+        let mut doc = serde_json::json!({"mode": "copy"});
+        let value = ConfigKey::Members
+            .parse_values(&["apps/web".into(), "apps/admin".into()])
+            .unwrap();
+        let config: WorkspaceConfig =
+            update_config_doc(&mut doc, ConfigKey::Members, Some(value)).unwrap();
+        assert_eq!(config.members.unwrap(), vec!["apps/web", "apps/admin"]);
+
+        let config: WorkspaceConfig = update_config_doc(&mut doc, ConfigKey::Mode, None).unwrap();
+        assert_eq!(config.mode, None);
+    }
+
+    #[test]
+    fn test_workspace_config_unknown_field() {
+        assert!(serde_json::from_str::<WorkspaceConfig>(r#"{"mdoe": "copy"}"#).is_err());
+    }
+
     #[test]
     fn test_config_health_check_not_exists() {
         let _result = Config::healthcheck();
@@ -154,6 +672,54 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_config_parse_invalid_field() {
+        let content = r#"{
+  "mode": "hardlink",
+  "sources": []
+}"#;
+        let err = Config::parse(content, Path::new("config.json")).unwrap_err();
+
+        let HealthCheckError::ConfigFileInvalid {
+            line,
+            field,
+            message,
+            ..
+        } = err
+        else {
+            panic!("expect invalid config error");
+        };
+        assert_eq!(line, 2);
+        assert_eq!(field, "mode");
+        assert!(message.starts_with("unknown variant `hardlink`"));
+    }
+
+    #[test]
+    fn test_config_parse_unknown_field() {
+        let content = r#"{
+  "mode": "copy",
+  "pickr": "inquire",
+  "sources": []
+}"#;
+        let err = Config::parse(content, Path::new("config.json")).unwrap_err();
+
+        let HealthCheckError::ConfigFileInvalid { line, message, .. } = err else {
+            panic!("expect invalid config error");
+        };
+        assert_eq!(line, 3);
+        assert!(message.starts_with("unknown field `pickr`"));
+    }
+
+    #[test]
+    fn test_config_parse_invalid_json() {
+        let err =
+            Config::parse("{\n  \"mode\": \"copy\",\n", Path::new("config.json")).unwrap_err();
+        assert!(matches!(
+            err,
+            HealthCheckError::ConfigFileInvalid { line: 3, .. }
+        ));
+    }
+
     #[test]
     fn test_workspace_root_home_env() {
         let test_home = PathBuf::from(env!("CARGO_WORKSPACE_DIR"));
@@ -165,7 +731,7 @@ mod tests {
         let config_root_path = Config::root_config_path();
         assert_eq!(
             config_root_path,
-            &PathBuf::from(env!("CARGO_WORKSPACE_DIR")).join(".npmpink.json")
+            &PathBuf::from(env!("CARGO_WORKSPACE_DIR")).join(".config/npmpink/config.json")
         );
     }
 }
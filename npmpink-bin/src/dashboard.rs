@@ -0,0 +1,158 @@
+use crate::cli::{package_formatters, sync_target, unsync_package};
+use crate::config::{appConfig, Config};
+use crate::preview::find_package;
+use anyhow::Result;
+use npmpink_core::item_display::{PackageItemDisplay, SourceItemDisplay};
+use npmpink_core::item_formatter::SourceItemFormatter;
+use npmpink_core::ops::packages::packages_from_source;
+use npmpink_core::package::Package;
+use npmpink_core::source::Source;
+use npmpink_core::target::Target;
+use npmpink_core::target_registry::TargetRegistry;
+use npmpink_tui::dashboard::DashboardActions;
+use npmpink_tui::shell::shell;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Dashboard of the target in dir, config and lockfile are read again on
+/// every call so changes of other `npk` runs show up, sources are scanned
+/// again only if they change.
+pub(crate) struct TargetDashboard {
+    dir: PathBuf,
+    scan: Option<SourceScan>,
+}
+
+/// Packages of sources, with the sources and their mtime when scanned.
+struct SourceScan {
+    sources: Vec<(Source, Option<SystemTime>)>,
+    packages: Vec<Package>,
+}
+
+impl SourceScan {
+    fn new(sources: &[Source]) -> Self {
+        let mut packages = sources
+            .iter()
+            .flat_map(packages_from_source)
+            .collect::<Vec<Package>>();
+        // keep the order stable between reloads.
+        packages.sort_by(|a, b| (&a.source_id, &a.name).cmp(&(&b.source_id, &b.name)));
+        SourceScan {
+            sources: stamps(sources),
+            packages,
+        }
+    }
+}
+
+/// Mtime of the source dirs, which changes when packages or tarballs are
+/// added or removed.
+fn stamps(sources: &[Source]) -> Vec<(Source, Option<SystemTime>)> {
+    sources
+        .iter()
+        .map(|s| {
+            let mtime = fs::metadata(&s.path).and_then(|m| m.modified()).ok();
+            (s.clone(), mtime)
+        })
+        .collect()
+}
+
+impl TargetDashboard {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        TargetDashboard { dir, scan: None }
+    }
+
+    fn target(&self) -> Target {
+        Target::init_from_dir(&self.dir)
+    }
+
+    /// Packages of sources, scanned again if sources changed.
+    fn scanned(&mut self, sources: &[Source]) -> &[Package] {
+        let scan = self
+            .scan
+            .take()
+            .filter(|scan| scan.sources == stamps(sources))
+            .unwrap_or_else(|| SourceScan::new(sources));
+        &self.scan.insert(scan).packages
+    }
+}
+
+impl DashboardActions for TargetDashboard {
+    fn target(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn sources(&mut self) -> Result<Vec<SourceItemDisplay>> {
+        let mut config = appConfig.lock().unwrap();
+        *config = Config::load()?;
+        Ok(config
+            .sources
+            .iter()
+            .map(|s| SourceItemFormatter::new(s).into())
+            .collect())
+    }
+
+    fn packages(&mut self) -> Result<Vec<PackageItemDisplay>> {
+        let config = appConfig.lock().unwrap();
+        let pkgs = self.scanned(&config.sources).to_vec();
+        Ok(package_formatters(pkgs, &config, &self.target())
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    fn rescan(&mut self) {
+        self.scan = None;
+    }
+
+    /// Only the lockfile is read, metadata comes from the last scan.
+    fn locked(&mut self) -> Result<Vec<PackageItemDisplay>> {
+        let config = appConfig.lock().unwrap();
+        let target = self.target();
+        let mut pkgs = target.lockfile()?.packages_iter().collect::<Vec<Package>>();
+        let scanned = self.scan.as_ref().map_or(&[][..], |s| &s.packages[..]);
+        for pkg in pkgs.iter_mut() {
+            if let Some(same) = scanned.iter().find(|s| *s == pkg) {
+                pkg.meta = same.meta.clone();
+            }
+        }
+        Ok(package_formatters(pkgs, &config, &target)
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    fn add(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+        let target = self.target();
+        let pkg = {
+            let config = appConfig.lock().unwrap();
+            find_package(
+                &config,
+                &target,
+                &format!("{}:{}", pkg.source_id, pkg.title),
+            )?
+        };
+        TargetRegistry::register(Config::targets_file(), target.workspace.absolute_dir()?)?;
+        target.update_lockfile(|lockfile| {
+            lockfile.add_package(pkg.name.clone(), pkg.clone());
+        })?;
+        shell()?.info(format!("added {}", pkg.name))
+    }
+
+    fn remove(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+        self.target().update_lockfile(|lockfile| {
+            lockfile.remove_package(pkg.title.clone());
+        })?;
+        shell()?.info(format!("removed {}", pkg.title))
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        let target = self.target();
+        TargetRegistry::register(Config::targets_file(), target.workspace.absolute_dir()?)?;
+        sync_target(&target)?;
+        shell()?.info("synced")
+    }
+
+    fn unsync(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+        unsync_package(&self.target(), &pkg.title)
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+static CONFIG_FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use the config file given by `--config`, must be called before the config
+/// is loaded.
+pub(crate) fn set_config_file_override(path: PathBuf) {
+    let _ = CONFIG_FILE_OVERRIDE.set(path);
+}
+
+pub(crate) fn config_file_override() -> Option<&'static PathBuf> {
+    CONFIG_FILE_OVERRIDE.get()
+}
+
+/// Where npmpink stores it's files.
+///
+/// `--config` > `NPMPINK_HOME` > XDG base dirs (`XDG_CONFIG_HOME`,
+/// `XDG_DATA_HOME`, `XDG_CACHE_HOME`) > `~/.config`, `~/.local/share`, `~/.cache`.
+/// With `--config`, data and cache dirs are next to the config file like
+/// with `NPMPINK_HOME`, so an isolated config never touches the shared data.
+#[derive(Debug, PartialEq)]
+pub(crate) struct NpkDirs {
+    pub config_file: PathBuf,
+    /// Data that can not be recreated, like git worktrees.
+    pub data_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    /// The config file is at the default place, the legacy config file
+    /// should be migrated into it.
+    pub is_default_config_file: bool,
+}
+
+impl NpkDirs {
+    pub fn resolve(
+        home: &Path,
+        env: impl Fn(&str) -> Option<String>,
+        config_file: Option<&Path>,
+    ) -> Self {
+        // relative paths are invalid by XDG spec.
+        let var = |key: &str| env(key).map(PathBuf::from).filter(|p| p.is_absolute());
+
+        let mut dirs = match var("NPMPINK_HOME") {
+            Some(npk_home) => NpkDirs {
+                config_file: npk_home.join("config.json"),
+                data_dir: npk_home.join("data"),
+                cache_dir: npk_home.join("cache"),
+                is_default_config_file: false,
+            },
+            None => NpkDirs {
+                config_file: var("XDG_CONFIG_HOME")
+                    .unwrap_or_else(|| home.join(".config"))
+                    .join("npmpink")
+                    .join("config.json"),
+                data_dir: var("XDG_DATA_HOME")
+                    .unwrap_or_else(|| home.join(".local").join("share"))
+                    .join("npmpink"),
+                cache_dir: var("XDG_CACHE_HOME")
+                    .unwrap_or_else(|| home.join(".cache"))
+                    .join("npmpink"),
+                is_default_config_file: true,
+            },
+        };
+
+        if let Some(config_file) = config_file {
+            let dir = config_file.parent().unwrap_or(Path::new("/"));
+            dirs = NpkDirs {
+                config_file: config_file.to_path_buf(),
+                data_dir: dir.join("data"),
+                cache_dir: dir.join("cache"),
+                is_default_config_file: false,
+            };
+        }
+
+        dirs
+    }
+
+    /// Config file before XDG support.
+    pub fn legacy_config_file(home: &Path) -> PathBuf {
+        home.join(".npmpink.json")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<String, String>>();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_resolve_default_dirs() {
+        let dirs = NpkDirs::resolve(Path::new("/home/me"), env_of(&[]), None);
+
+        assert_eq!(
+            dirs.config_file,
+            PathBuf::from("/home/me/.config/npmpink/config.json")
+        );
+        assert_eq!(
+            dirs.data_dir,
+            PathBuf::from("/home/me/.local/share/npmpink")
+        );
+        assert_eq!(dirs.cache_dir, PathBuf::from("/home/me/.cache/npmpink"));
+        assert!(dirs.is_default_config_file);
+    }
+
+    #[test]
+    fn test_resolve_xdg_dirs() {
+        let env = env_of(&[
+            ("XDG_CONFIG_HOME", "/xdg/config"),
+            ("XDG_DATA_HOME", "relative/is/ignored"),
+        ]);
+        let dirs = NpkDirs::resolve(Path::new("/home/me"), env, None);
+
+        assert_eq!(
+            dirs.config_file,
+            PathBuf::from("/xdg/config/npmpink/config.json")
+        );
+        assert_eq!(
+            dirs.data_dir,
+            PathBuf::from("/home/me/.local/share/npmpink")
+        );
+    }
+
+    #[test]
+    fn test_resolve_npmpink_home_and_config_override() {
+        let env = env_of(&[("NPMPINK_HOME", "/ci/npk"), ("XDG_CONFIG_HOME", "/xdg")]);
+        let dirs = NpkDirs::resolve(Path::new("/home/me"), &env, None);
+
+        assert_eq!(dirs.config_file, PathBuf::from("/ci/npk/config.json"));
+        assert_eq!(dirs.data_dir, PathBuf::from("/ci/npk/data"));
+        assert!(!dirs.is_default_config_file);
+
+        let config_file = Path::new("/work/ci/a.json");
+        let dirs = NpkDirs::resolve(Path::new("/home/me"), &env, Some(config_file));
+        assert_eq!(dirs.config_file, PathBuf::from("/work/ci/a.json"));
+        assert_eq!(dirs.data_dir, PathBuf::from("/work/ci/data"));
+        assert_eq!(dirs.cache_dir, PathBuf::from("/work/ci/cache"));
+
+        // the shared data is not used even without NPMPINK_HOME.
+        let dirs = NpkDirs::resolve(Path::new("/home/me"), env_of(&[]), Some(config_file));
+        assert_eq!(dirs.data_dir, PathBuf::from("/work/ci/data"));
+        assert!(!dirs.is_default_config_file);
+    }
+}
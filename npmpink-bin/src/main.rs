@@ -1,10 +1,24 @@
 mod cli;
+mod completions;
 mod config;
+mod dashboard;
+mod dirs;
+mod output;
+mod preview;
+mod serve;
 
+use clap::CommandFactory;
+use clap_complete::CompleteEnv;
 use cli::*;
 use npmpink_tui::shell::shell;
+use output::ErrorOutput;
 
 fn main() {
+    // must run before anything is printed, shells read completions from stdout.
+    CompleteEnv::with_factory(Cli::command)
+        .var(completions::COMPLETE_VAR)
+        .complete();
+
     let result = run();
 
     if let Err(e) = result {
@@ -21,7 +35,12 @@ fn handle_error(e: anyhow::Error) {
     }
 
     if let Ok(ref mut sh) = shell() {
-        let _ = sh.error(&format!("{}", e));
+        // in JSON mode, commands that printed their output report errors in it.
+        if sh.is_json() && !sh.is_json_printed() {
+            let _ = sh.json(&serde_json::json!({ "error": ErrorOutput::from(&e) }));
+        } else {
+            let _ = sh.error(format!("{:#}", e));
+        }
     }
     std::process::exit(1);
 }
@@ -0,0 +1,231 @@
+//! Output of `--json`. Fields may be added to these structures but are never
+//! renamed or removed, so editor integrations can rely on them.
+
+use crate::config::{Config, HealthCheckError};
+use npmpink_core::git::GitSource;
+use npmpink_core::package::Package;
+use npmpink_core::source::{Source, SourceHealth, SourceKind};
+use npmpink_core::target::Target;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Stable code of errors, printed as `{"error": {"code": .., "message": ..}}`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorCode {
+    ConfigNotFound,
+    ConfigUnreadable,
+    ConfigInvalid,
+    InvalidArgument,
+    NotFound,
+    AlreadyExists,
+    /// Package name is in multiple sources.
+    Ambiguous,
+    /// Confirmation is needed but stdin is not a terminal.
+    InteractiveRequired,
+    /// External command like the package manager or a pre sync script failed.
+    CommandFailed,
+    CheckFailed,
+    /// Source is missing or unreadable, or package references such source.
+    SourceUnavailable,
+    /// File was changed by another run while it's edited.
+    Conflict,
+    /// Any other error.
+    Error,
+}
+
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub(crate) struct CliError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(code: ErrorCode, message: impl fmt::Display) -> Self {
+        CliError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ErrorOutput {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl From<&anyhow::Error> for ErrorOutput {
+    fn from(e: &anyhow::Error) -> Self {
+        ErrorOutput {
+            code: error_code(e),
+            message: format!("{:#}", e),
+        }
+    }
+}
+
+fn error_code(e: &anyhow::Error) -> ErrorCode {
+    for cause in e.chain() {
+        if let Some(e) = cause.downcast_ref::<CliError>() {
+            return e.code;
+        }
+        if let Some(e) = cause.downcast_ref::<HealthCheckError>() {
+            return match e {
+                HealthCheckError::ConfigFileNotExist => ErrorCode::ConfigNotFound,
+                HealthCheckError::ConfigFileUnreadable { .. } => ErrorCode::ConfigUnreadable,
+                HealthCheckError::ConfigFileInvalid { .. } => ErrorCode::ConfigInvalid,
+            };
+        }
+    }
+    ErrorCode::Error
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SourceOutput {
+    pub id: String,
+    pub path: PathBuf,
+    pub kind: SourceKind,
+    pub git: Option<GitSource>,
+    pub health: SourceHealth,
+}
+
+impl From<&Source> for SourceOutput {
+    fn from(source: &Source) -> Self {
+        SourceOutput {
+            id: source.id.clone(),
+            path: source.path.clone(),
+            kind: source.kind,
+            git: source.git.clone(),
+            health: source.health(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PackageOutput {
+    pub name: String,
+    /// Version in package.json, none if the package is not in sources.
+    pub version: Option<String>,
+    pub dir: String,
+    pub source_id: String,
+    /// Path of the source, none if the source is removed.
+    pub source: Option<PathBuf>,
+    /// In the lockfile of current target.
+    pub added: bool,
+    /// Exists in `node_modules` of current target.
+    pub linked: bool,
+}
+
+impl PackageOutput {
+    pub fn new(pkg: &Package, config: &Config, target: &Target) -> Self {
+        PackageOutput {
+            name: pkg.name.clone(),
+            version: pkg.meta().and_then(|m| m.version.clone()),
+            dir: pkg.dir.clone(),
+            source_id: pkg.source_id.clone(),
+            source: config
+                .sources
+                .iter()
+                .find(|s| s.id == pkg.source_id)
+                .map(|s| s.path.clone()),
+            added: is_added(target, &pkg.name),
+            linked: is_linked(target, &pkg.name),
+        }
+    }
+}
+
+/// Package is in the lockfile of target.
+pub(crate) fn is_added(target: &Target, name: &str) -> bool {
+    target
+        .lockfile()
+        .is_ok_and(|lockfile| lockfile.packages.contains_key(name))
+}
+
+/// Package exists in `node_modules` of target.
+pub(crate) fn is_linked(target: &Target, name: &str) -> bool {
+    std::fs::symlink_metadata(target.workspace.dir.join("node_modules").join(name)).is_ok()
+}
+
+/// How a package is put into `node_modules`.
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SyncMethod {
+    Link,
+    Copy,
+    Extract,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncedPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub method: SyncMethod,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncedMember {
+    pub dir: PathBuf,
+    pub packages: Vec<SyncedPackage>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncOutput {
+    pub target: PathBuf,
+    pub pre_sync: Vec<String>,
+    pub members: Vec<SyncedMember>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncFailure {
+    pub target: PathBuf,
+    pub error: ErrorOutput,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncAllOutput {
+    pub synced: Vec<SyncOutput>,
+    pub failed: Vec<SyncFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct TargetOutput {
+    pub dir: PathBuf,
+    pub packages: usize,
+    /// Why the lockfile of target can't be read.
+    pub error: Option<ErrorOutput>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CheckOutput {
+    pub ok: bool,
+    pub problems: Vec<ErrorOutput>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_error_output_code() {
+        let e = anyhow::Error::new(CliError::new(ErrorCode::NotFound, "no packages match `a`"))
+            .context("failed to add");
+        let output = ErrorOutput::from(&e);
+        assert_eq!(output.code, ErrorCode::NotFound);
+        assert_eq!(output.message, "failed to add: no packages match `a`");
+
+        let e = Err::<(), _>(HealthCheckError::ConfigFileNotExist)
+            .context("refuse to overwrite")
+            .unwrap_err();
+        assert_eq!(ErrorOutput::from(&e).code, ErrorCode::ConfigNotFound);
+
+        let e = anyhow::anyhow!("io error");
+        assert_eq!(
+            serde_json::to_value(ErrorOutput::from(&e)).unwrap()["code"],
+            "error"
+        );
+    }
+}
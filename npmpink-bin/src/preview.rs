@@ -0,0 +1,168 @@
+use crate::config::Config;
+use crate::output::{is_added, is_linked};
+use anyhow::{Context, Result};
+use npmpink_core::git::current_branch;
+use npmpink_core::ops::packages::packages_from_source;
+use npmpink_core::package::Package;
+use npmpink_core::source::Source;
+use npmpink_core::target::Target;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Package of preview id `<source id>:<package name>`, packages of removed
+/// sources are looked up in the lockfile, they have no metadata.
+pub(crate) fn find_package(config: &Config, target: &Target, id: &str) -> Result<Package> {
+    let (source_id, name) = id
+        .split_once(':')
+        .with_context(|| format!("invalid preview id {}", id))?;
+    let is_package = |pkg: &Package| pkg.source_id == source_id && pkg.name == name;
+
+    if let Some(pkg) = config
+        .sources
+        .iter()
+        .filter(|s| s.id == source_id)
+        .flat_map(packages_from_source)
+        .find(is_package)
+    {
+        return Ok(pkg);
+    }
+    target
+        .lockfile()?
+        .packages_iter()
+        .find(is_package)
+        .with_context(|| format!("package {} not found in sources", name))
+}
+
+/// Details of the package shown next to pickers.
+pub(crate) fn package_preview(pkg: &Package, source: Option<&Source>, target: &Target) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{} {}", pkg.name, pkg.version_label());
+    if let Some(description) = pkg.meta().and_then(|m| m.description.as_ref()) {
+        let _ = writeln!(out, "{}", description);
+    }
+    let _ = writeln!(out);
+
+    let mut row = |label: &str, value: String| {
+        let _ = writeln!(out, "{:<9} {}", label, value);
+    };
+    row(
+        "source",
+        source.map_or("<removed>".to_owned(), |s| s.path.display().to_string()),
+    );
+    if let Some(branch) = source.and_then(source_branch) {
+        row("branch", branch);
+    }
+    row("dir", pkg.dir.clone());
+    let meta = pkg.meta().cloned().unwrap_or_default();
+    for (key, value) in [
+        ("main", &meta.main),
+        ("module", &meta.module),
+        ("types", &meta.types),
+    ] {
+        if let Some(value) = value {
+            row(key, value.clone());
+        }
+    }
+    if let Some(ref exports) = meta.exports {
+        row("exports", exports_summary(exports));
+    }
+    if !meta.bin.is_empty() {
+        row(
+            "bin",
+            meta.bin.keys().cloned().collect::<Vec<String>>().join(", "),
+        );
+    }
+    row("added", yes_no(is_added(target, &pkg.name)));
+    row("linked", yes_no(is_linked(target, &pkg.name)));
+
+    for (key, deps) in [
+        ("dependencies", &meta.dependencies),
+        ("peerDependencies", &meta.peer_dependencies),
+    ] {
+        if deps.is_empty() {
+            continue;
+        }
+        let _ = writeln!(out, "\n{}", key);
+        for (name, version) in deps {
+            let _ = writeln!(out, "  {} {}", name, version);
+        }
+    }
+
+    out
+}
+
+/// Ref of git source, or branch of the repository the source is in.
+fn source_branch(source: &Source) -> Option<String> {
+    match source.git {
+        Some(ref git) => Some(git.to_string()),
+        None => current_branch(&source.path),
+    }
+}
+
+/// Subpaths of `exports`, or `.` if it only has conditions like `import`.
+fn exports_summary(exports: &Value) -> String {
+    match exports {
+        Value::String(path) => path.clone(),
+        Value::Object(map) if map.keys().all(|k| k.starts_with('.')) => {
+            map.keys().cloned().collect::<Vec<String>>().join(", ")
+        }
+        _ => ".".to_owned(),
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use npmpink_core::package::PackageMeta;
+    use npmpink_core::test_util::TempDir;
+    use npmpink_core::workspace::Workspace;
+    use std::fs;
+
+    #[test]
+    fn test_package_preview() {
+        let root = TempDir::new("preview");
+        let pkg_dir = root.join("libs/ui");
+        let target_dir = root.join("web");
+        fs::create_dir_all(&pkg_dir).unwrap();
+        fs::create_dir_all(target_dir.join("node_modules/@acme/ui")).unwrap();
+        fs::write(
+            pkg_dir.join("package.json"),
+            r#"{
+                "name": "@acme/ui",
+                "version": "1.2.0",
+                "description": "Buttons",
+                "main": "dist/index.js",
+                "bin": "bin/ui.js",
+                "exports": {".": "./dist/index.js", "./button": "./dist/button.js"},
+                "peerDependencies": {"react": "^18"}
+            }"#,
+        )
+        .unwrap();
+
+        let pkg = Package::new(
+            "@acme/ui".into(),
+            pkg_dir.to_string_lossy().into(),
+            "1".into(),
+        );
+        let target = Target::init_from_dir(&target_dir);
+        assert!(package_preview(&pkg, None, &target).starts_with("@acme/ui unknown\n\n"));
+
+        let meta = PackageMeta::from_package_json(
+            Workspace::init_from_dir(&pkg_dir).package_json().unwrap(),
+        );
+        let preview = package_preview(&pkg.with_meta(meta), None, &target);
+
+        assert!(preview.starts_with("@acme/ui 1.2.0\nButtons\n"));
+        assert!(preview.contains("source    <removed>\n"));
+        assert!(preview.contains("main      dist/index.js\n"));
+        assert!(preview.contains("exports   ., ./button\n"));
+        assert!(preview.contains("bin       ui\n"));
+        assert!(preview.contains("added     no\n"));
+        assert!(preview.contains("linked    yes\n"));
+        assert!(preview.contains("\npeerDependencies\n  react ^18\n"));
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use npmpink_core::registry::{Registry, RegistryRequest};
+use npmpink_tui::shell::shell;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub(crate) struct ServeArgs {
+    pub host: String,
+    pub port: u16,
+    /// Registry to redirect to for packages not in sources.
+    pub upstream: Option<String>,
+}
+
+/// Serve the registry until the process is killed.
+pub(crate) fn serve(registry: Registry, args: &ServeArgs) -> Result<()> {
+    let addr = format!("{}:{}", args.host, args.port);
+    let server = Server::http(&addr).map_err(anyhow::Error::msg)?;
+    let base_url = format!("http://{}", addr);
+
+    shell()?.info(format!(
+        "serving {} packages at {}, install with `--registry {}`",
+        registry.len(),
+        base_url,
+        base_url
+    ))?;
+
+    for request in server.incoming_requests() {
+        let url = request.url().to_owned();
+        let result = handle_request(&registry, request, &base_url, args);
+
+        if let Err(e) = result {
+            shell()?.error(format!("{}: {}", url, e))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_request(
+    registry: &Registry,
+    request: Request,
+    base_url: &str,
+    args: &ServeArgs,
+) -> Result<()> {
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return Ok(request.respond(Response::empty(405))?);
+    }
+
+    let found = match RegistryRequest::from_url_path(request.url()) {
+        Some(RegistryRequest::Packument(ref name)) => registry
+            .packument(name, base_url)?
+            .map(|doc| response(doc.to_string().into_bytes(), "application/json")),
+        Some(RegistryRequest::Tarball(ref name)) => registry
+            .tarball(name)?
+            .map(|tarball| response(tarball, "application/octet-stream")),
+        None => None,
+    };
+
+    if let Some(found) = found {
+        shell()?.info(format!("200 {}", request.url()))?;
+        return Ok(request.respond(found)?);
+    }
+
+    match args.upstream {
+        Some(ref upstream) => {
+            let location = format!("{}{}", upstream.trim_end_matches('/'), request.url());
+            let header = Header::from_bytes("Location", location)
+                .map_err(|_| anyhow!("invalid upstream url {}", upstream))?;
+            Ok(request.respond(Response::empty(302).with_header(header))?)
+        }
+        None => Ok(request.respond(Response::from_string("not found").with_status_code(404))?),
+    }
+}
+
+fn response(body: Vec<u8>, content_type: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    Response::from_data(body).with_header(header)
+}
@@ -0,0 +1,130 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Exclusive advisory lock of a file, held until dropped.
+///
+/// The lock is taken on a sidecar `.<name>.lock` file instead of the file
+/// itself, because `write_atomic` replaces the file with a new inode.
+/// The lock is not reentrant, don't acquire it twice in one process.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Block until the lock of path is acquired.
+    pub fn acquire(path: impl AsRef<Path>) -> Result<Self> {
+        let lock_path = lock_path(path.as_ref());
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("failed to open {}", lock_path.display()))?;
+        file.lock()
+            .with_context(|| format!("failed to lock {}", lock_path.display()))?;
+
+        Ok(FileLock { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.lock", name))
+}
+
+/// Write to a temp file next to path and rename it over path, so readers
+/// never see a partially written file.
+pub fn write_atomic(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<()> {
+    let path = path.as_ref();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.tmp-{}", name, std::process::id()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let write = || -> Result<()> {
+        let mut file = File::create(&temp)?;
+        file.write_all(content.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    };
+    write().map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        e.context(format!("failed to write {}", path.display()))
+    })
+}
+
+/// Json file parsed as `T`, or the default if the file doesn't exist yet.
+pub fn load_json<T: DeserializeOwned + Default>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    if !path.try_exists()? {
+        return Ok(T::default());
+    }
+    serde_json::from_str(&fs::read_to_string(path)?)
+        .with_context(|| format!("invalid json file {}", path.display()))
+}
+
+/// Reload the json file while holding its lock, change it with `f` and write
+/// it back if changed, so concurrent runs don't lose updates.
+pub fn update_json<T, R>(path: impl AsRef<Path>, f: impl FnOnce(&mut T) -> R) -> Result<R>
+where
+    T: DeserializeOwned + Serialize + Default,
+{
+    let path = path.as_ref();
+    let _lock = FileLock::acquire(path)?;
+    let mut value = load_json::<T>(path)?;
+
+    let before = serde_json::to_string_pretty(&value)?;
+    let result = f(&mut value);
+    let content = serde_json::to_string_pretty(&value)?;
+    if content != before {
+        write_atomic(path, content)?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs::TryLockError;
+
+    #[test]
+    fn test_lock_and_write_atomic() {
+        let root = TempDir::new("file-lock");
+        let path = root.join("config.json");
+
+        let lock = FileLock::acquire(&path).unwrap();
+        let other = File::open(lock_path(&path)).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+
+        write_atomic(&path, "{}").unwrap();
+        write_atomic(&path, r#"{"mode": "copy"}"#).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"mode": "copy"}"#);
+
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+
+        let mut names = fs::read_dir(root.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        names.sort();
+        assert_eq!(names, vec![".config.json.lock", "config.json"]);
+    }
+}
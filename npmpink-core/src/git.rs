@@ -0,0 +1,246 @@
+use crate::source::hash_path_string;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Where a git source is checked out from.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct GitSource {
+    /// Repository url or absolute path of local repository.
+    pub url: String,
+    /// Branch, tag or commit.
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+}
+
+impl GitSource {
+    pub fn new(url: impl Into<String>, git_ref: impl Into<String>) -> Self {
+        GitSource {
+            url: url.into(),
+            git_ref: git_ref.into(),
+        }
+    }
+
+    /// Bare mirror of the repository, shared by all refs of same url.
+    pub fn mirror_dir(&self, git_root: &Path) -> PathBuf {
+        git_root
+            .join("repos")
+            .join(format!("{}.git", hash_path_string(self.url.clone())))
+    }
+
+    /// Worktree dir of the ref, this is the source path. Refs like
+    /// `feat/foo` and `feat-foo` have the same label, the hash of the ref
+    /// keeps their dirs apart.
+    pub fn worktree_dir(&self, git_root: &Path) -> PathBuf {
+        let ref_label = self
+            .git_ref
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect::<String>();
+        let ref_hash = hash_path_string(self.git_ref.clone())
+            .chars()
+            .take(8)
+            .collect::<String>();
+
+        git_root.join("worktrees").join(format!(
+            "{}-{}-{}",
+            hash_path_string(self.url.clone()),
+            ref_label,
+            ref_hash
+        ))
+    }
+
+    /// Fetch the repository into mirror and checkout the ref into worktree,
+    /// existing worktree is updated to latest of the ref.
+    ///
+    /// Only `git` is required, works offline against local or bare repos.
+    pub fn materialize(&self, git_root: &Path) -> Result<PathBuf> {
+        let mirror = self.mirror_dir(git_root);
+        let worktree = self.worktree_dir(git_root);
+
+        if !mirror.exists() {
+            std::fs::create_dir_all(&mirror)?;
+            git(&mirror, &["init", "--bare", "--quiet"])?;
+        }
+        git(
+            &mirror,
+            &[
+                "fetch",
+                "--quiet",
+                "--prune",
+                "--force",
+                "--tags",
+                &self.url,
+                "+refs/heads/*:refs/heads/*",
+                // branches of others in a local clone are remote refs, like
+                // `origin/feat`.
+                "+refs/remotes/*:refs/remotes/*",
+            ],
+        )
+        .with_context(|| format!("failed to fetch {}", self.url))?;
+
+        let commit = git(
+            &mirror,
+            &[
+                "rev-parse",
+                "--verify",
+                &format!("{}^{{commit}}", self.git_ref),
+            ],
+        )
+        .with_context(|| format!("ref {} not found in {}", self.git_ref, self.url))?;
+
+        if worktree.exists() {
+            git(
+                &worktree,
+                &["checkout", "--quiet", "--force", "--detach", &commit],
+            )?;
+        } else {
+            git(
+                &mirror,
+                &[
+                    "worktree",
+                    "add",
+                    "--quiet",
+                    "--force",
+                    "--detach",
+                    &worktree.to_string_lossy(),
+                    &commit,
+                ],
+            )?;
+        }
+
+        Ok(worktree)
+    }
+
+    /// Remove the worktree of source path, the mirror is kept for other refs.
+    pub fn remove_worktree(&self, git_root: &Path, worktree: &Path) -> Result<()> {
+        if !worktree.exists() {
+            return Ok(());
+        }
+        git(
+            &self.mirror_dir(git_root),
+            &["worktree", "remove", "--force", &worktree.to_string_lossy()],
+        )?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for GitSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}#{}", self.url, self.git_ref)
+    }
+}
+
+/// Whether the string looks like a remote git url instead of a local path.
+pub fn is_git_url(url: &str) -> bool {
+    url.contains("://") || (url.contains('@') && url.contains(':'))
+}
+
+/// Branch checked out in the dir, none if it's not a git repository or
+/// the HEAD is detached.
+pub fn current_branch(dir: impl AsRef<Path>) -> Option<String> {
+    git(
+        dir.as_ref(),
+        &["symbolic-ref", "--quiet", "--short", "HEAD"],
+    )
+    .ok()
+}
+
+/// Run git in dir, returns trimmed stdout.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("failed to run git, is it installed?")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_is_git_url() {
+        assert!(is_git_url("https://github.com/towry/npmpink.git"));
+        assert!(is_git_url("git@github.com:towry/npmpink.git"));
+        assert!(is_git_url("file:///tmp/repo"));
+        assert!(!is_git_url("/tmp/repo"));
+    }
+
+    #[test]
+    fn test_worktree_dir_of_ref() {
+        let source = GitSource::new("/tmp/repo", "feat/foo");
+        let dir = source.worktree_dir(Path::new("/git"));
+
+        assert!(dir.starts_with("/git/worktrees"));
+        assert!(dir.to_string_lossy().contains("-feat-foo-"));
+        assert_ne!(
+            dir,
+            GitSource::new("/tmp/repo", "feat-foo").worktree_dir(Path::new("/git"))
+        );
+    }
+
+    #[test]
+    fn test_materialize_local_repo() {
+        let root = TempDir::new("git");
+        let repo = root.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+
+        let setup = [
+            vec!["init", "--quiet", "--initial-branch", "main"],
+            vec!["config", "user.email", "test@npmpink"],
+            vec!["config", "user.name", "test"],
+        ];
+        if setup.iter().any(|args| git(&repo, args).is_err()) {
+            // git is not available.
+            return;
+        }
+        fs::write(repo.join("package.json"), r#"{"name": "foo"}"#).unwrap();
+        git(&repo, &["add", "."]).unwrap();
+        git(&repo, &["commit", "--quiet", "-m", "init"]).unwrap();
+
+        let source = GitSource::new(repo.to_string_lossy(), "main");
+        let worktree = source.materialize(&root.join("git")).unwrap();
+        assert!(worktree.join("package.json").exists());
+        assert_eq!(current_branch(&repo).as_deref(), Some("main"));
+        // worktree is detached at the commit of ref.
+        assert_eq!(current_branch(&worktree), None);
+
+        // update existing worktree.
+        fs::write(repo.join("index.js"), "").unwrap();
+        git(&repo, &["add", "."]).unwrap();
+        git(&repo, &["commit", "--quiet", "-m", "index"]).unwrap();
+        source.materialize(&root.join("git")).unwrap();
+        assert!(worktree.join("index.js").exists());
+
+        // branch of others in a local clone.
+        git(&repo, &["branch", "feat/foo"]).unwrap();
+        git(
+            root.path(),
+            &["clone", "--quiet", &repo.to_string_lossy(), "clone"],
+        )
+        .unwrap();
+        let remote = GitSource::new(root.join("clone").to_string_lossy(), "origin/feat/foo");
+        let remote_worktree = remote.materialize(&root.join("git")).unwrap();
+        assert!(remote_worktree.join("index.js").exists());
+
+        source
+            .remove_worktree(&root.join("git"), &worktree)
+            .unwrap();
+        assert!(!worktree.exists());
+    }
+}
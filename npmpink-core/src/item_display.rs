@@ -1,12 +1,43 @@
-/// |package_name|source_dir_tail|source_id|
+use crate::source::SourceHealth;
+
+/// |package_name|version|source_dir_tail|dir_in_source|status|source_id|
 #[derive(Clone)]
 pub struct PackageItemDisplay {
     pub title: String,
+    pub version: String,
     pub source_label: String,
+    /// Dir of the package relative to it's source.
+    pub path: String,
+    pub status: PackageStatus,
     pub source_id: String,
 }
 
+/// Whether the package is in the lockfile or `node_modules` of the target.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PackageStatus {
+    #[default]
+    Available,
+    Added,
+    Linked,
+}
+
+impl PackageStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PackageStatus::Available => "",
+            PackageStatus::Added => "added",
+            PackageStatus::Linked => "linked",
+        }
+    }
+}
+
+/// |source_path|source_dir_tail|kind|health|source_id|
 #[derive(Clone)]
 pub struct SourceItemDisplay {
     pub title: String,
+    pub label: String,
+    /// `dir`, `tarball` or `<git url>#<ref>`.
+    pub kind: String,
+    pub health: SourceHealth,
+    pub id: String,
 }
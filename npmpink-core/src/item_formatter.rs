@@ -1,35 +1,162 @@
-use crate::item_display::PackageItemDisplay;
+use crate::item_display::{PackageItemDisplay, PackageStatus, SourceItemDisplay};
 use crate::package::Package;
-use crate::source::Source;
+use crate::source::{Source, SourceKind};
+use std::path::Path;
 use std::rc::Rc;
 
-struct SourceItemFormatter {}
+#[derive(Clone)]
+pub struct SourceItemFormatter<'a> {
+    pub inner: &'a Source,
+}
+
+impl<'a> SourceItemFormatter<'a> {
+    pub fn new(source: &'a Source) -> SourceItemFormatter<'a> {
+        SourceItemFormatter { inner: source }
+    }
+}
+
+impl<'a> From<SourceItemFormatter<'a>> for SourceItemDisplay {
+    fn from(val: SourceItemFormatter<'a>) -> Self {
+        SourceItemDisplay {
+            title: val.inner.path.display().to_string(),
+            label: source_label(val.inner).unwrap_or_default(),
+            kind: match val.inner.git {
+                Some(ref git) => git.to_string(),
+                None => match val.inner.kind {
+                    SourceKind::Dir => "dir".to_owned(),
+                    SourceKind::Tarball => "tarball".to_owned(),
+                },
+            },
+            health: val.inner.health(),
+            id: val.inner.id.clone(),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct PackageItemFormatter<'a> {
     pub inner: Rc<Package>,
-    pub source: &'a Source,
+    /// `None` if the source is no longer registered.
+    pub source: Option<&'a Source>,
+    pub status: PackageStatus,
 }
 
 impl<'a> PackageItemFormatter<'a> {
-    pub fn new(package: Rc<Package>, source: &'a Source) -> PackageItemFormatter<'a> {
+    pub fn new(package: Rc<Package>, source: Option<&'a Source>) -> PackageItemFormatter<'a> {
         PackageItemFormatter {
             inner: package,
             source,
+            status: PackageStatus::default(),
         }
     }
+
+    pub fn with_status(mut self, status: PackageStatus) -> PackageItemFormatter<'a> {
+        self.status = status;
+        self
+    }
 }
 
 impl<'a> From<PackageItemFormatter<'a>> for PackageItemDisplay {
     fn from(val: PackageItemFormatter<'a>) -> Self {
         PackageItemDisplay {
             title: val.inner.name.clone(),
-            source_label: source_label(val.source).unwrap_or("<unkown source>".to_owned()),
-            source_id: source_id(val.source).unwrap_or("<unkown source id>".to_owned()),
+            version: val.inner.version_label().to_owned(),
+            source_label: val
+                .source
+                .and_then(source_label)
+                .unwrap_or("<unkown source>".to_owned()),
+            path: val
+                .source
+                .and_then(|s| relative_dir(&val.inner, s))
+                .unwrap_or_else(|| val.inner.dir.clone()),
+            status: val.status,
+            source_id: val
+                .source
+                .and_then(source_id)
+                .unwrap_or("<unkown source id>".to_owned()),
         }
     }
 }
 
+/// Widths of the columns of package items, so items of a picker line up.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PackageColumns {
+    pub title: usize,
+    pub version: usize,
+    pub source_label: usize,
+    pub path: usize,
+}
+
+impl PackageColumns {
+    /// Columns wide enough for all items.
+    pub fn fit<'a>(items: impl IntoIterator<Item = &'a PackageItemDisplay>) -> Self {
+        items
+            .into_iter()
+            .fold(PackageColumns::default(), |columns, item| PackageColumns {
+                title: columns.title.max(width(&item.title)),
+                version: columns.version.max(width(&item.version)),
+                source_label: columns.source_label.max(width(&item.source_label)),
+                path: columns.path.max(width(&item.path)),
+            })
+    }
+
+    /// Cells of item padded to the column widths, the status is the last
+    /// column and not padded.
+    pub fn cells(&self, item: &PackageItemDisplay) -> [String; 5] {
+        [
+            pad(&item.title, self.title),
+            pad(&item.version, self.version),
+            pad(&item.source_label, self.source_label),
+            pad(&item.path, self.path),
+            item.status.label().to_owned(),
+        ]
+    }
+
+    /// Plain text row of item, columns separated by two spaces.
+    pub fn row(&self, item: &PackageItemDisplay) -> String {
+        self.cells(item).join("  ").trim_end().to_owned()
+    }
+}
+
+/// Indexes of items ordered by group, groups are in the order they first
+/// appear and items keep their order in a group.
+pub fn group_order<T, K: PartialEq>(items: &[T], group: impl Fn(&T) -> K) -> Vec<usize> {
+    let mut groups = Vec::<K>::new();
+    let mut ranks = Vec::with_capacity(items.len());
+    for item in items {
+        let key = group(item);
+        let rank = match groups.iter().position(|g| *g == key) {
+            Some(rank) => rank,
+            None => {
+                groups.push(key);
+                groups.len() - 1
+            }
+        };
+        ranks.push(rank);
+    }
+
+    let mut order = (0..items.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|i| ranks[*i]);
+    order
+}
+
+fn width(s: &str) -> usize {
+    s.chars().count()
+}
+
+fn pad(s: &str, width: usize) -> String {
+    format!("{:<width$}", s, width = width)
+}
+
+/// Dir of package relative to the source, `.` for the source root.
+fn relative_dir(pkg: &Package, source: &Source) -> Option<String> {
+    let relative = Path::new(&pkg.dir).strip_prefix(&source.path).ok()?;
+    if relative.as_os_str().is_empty() {
+        return Some(".".to_owned());
+    }
+    Some(relative.display().to_string())
+}
+
 fn source_label(source: &Source) -> Option<String> {
     source
         .path
@@ -41,3 +168,41 @@ fn source_label(source: &Source) -> Option<String> {
 fn source_id(source: &Source) -> Option<String> {
     Some(source.id.clone())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, version: &str, path: &str, status: PackageStatus) -> PackageItemDisplay {
+        PackageItemDisplay {
+            title: title.into(),
+            version: version.into(),
+            source_label: "libs".into(),
+            path: path.into(),
+            status,
+            source_id: "1".into(),
+        }
+    }
+
+    #[test]
+    fn test_package_columns() {
+        let items = [
+            item("@acme/ui", "1.2.0", "packages/ui", PackageStatus::Linked),
+            item("lodash", "", ".", PackageStatus::Available),
+        ];
+        let columns = PackageColumns::fit(&items);
+
+        assert_eq!(
+            columns.row(&items[0]),
+            "@acme/ui  1.2.0  libs  packages/ui  linked"
+        );
+        assert_eq!(columns.row(&items[1]), "lodash           libs  .");
+    }
+
+    #[test]
+    fn test_group_order() {
+        let sources = ["b", "a", "b", "c", "a"];
+        assert_eq!(group_order(&sources, |s| *s), vec![0, 2, 1, 4, 3]);
+        assert!(group_order(&[] as &[&str], |s| *s).is_empty());
+    }
+}
@@ -1,9 +1,17 @@
+pub mod file_lock;
+pub mod git;
 pub mod item_display;
 pub mod item_formatter;
 pub mod lockfile;
 pub mod ops;
 pub mod package;
+pub mod pick_history;
+pub mod registry;
 pub mod source;
+pub mod tarball;
 pub mod target;
+pub mod target_registry;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 pub mod walker;
 pub mod workspace;
@@ -5,6 +5,8 @@ use std::default::Default;
 use std::iter::Iterator;
 
 use crate::package::Package;
+use crate::source::Source;
+use std::path::Path;
 
 #[derive(PartialEq, Deserialize, Serialize, Debug)]
 pub struct LockfileContent {
@@ -30,6 +32,22 @@ impl LockfileContent {
         self.packages.remove(&pkg_name);
         self
     }
+    /// Point packages of `old` source to `new` source, returns how many
+    /// packages are updated.
+    pub fn relocate_source(&mut self, old: &Source, new: &Source) -> usize {
+        let mut count = 0;
+        for pkg in self.packages.values_mut() {
+            if pkg.source_id != old.id {
+                continue;
+            }
+            if let Ok(rest) = Path::new(&pkg.dir).strip_prefix(&old.path) {
+                pkg.dir = new.path.join(rest).to_string_lossy().into_owned();
+            }
+            pkg.source_id = new.id.clone();
+            count += 1;
+        }
+        count
+    }
     pub fn to_json_string(&self) -> Result<String> {
         serde_json::to_string_pretty(self).map_err(anyhow::Error::from)
     }
@@ -69,6 +87,30 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_relocate_source() {
+        use crate::source::Source;
+
+        let old = Source::new("/old/libs");
+        let new = Source::new("/new/libs");
+        let mut lockfile = LockfileContent::new();
+        lockfile.add_package(
+            "a".into(),
+            Package::new("a".into(), "/old/libs/packages/a".into(), old.id.clone()),
+        );
+        lockfile.add_package(
+            "b".into(),
+            Package::new("b".into(), "/other/b".into(), "other".into()),
+        );
+
+        assert_eq!(lockfile.relocate_source(&old, &new), 1);
+
+        let a = &lockfile.packages["a"];
+        assert_eq!(a.dir, "/new/libs/packages/a");
+        assert_eq!(a.source_id, new.id);
+        assert_eq!(lockfile.packages["b"].source_id, "other");
+    }
+
     #[test]
     fn test_serialize_lockfile_content() {
         use serde_test::{assert_tokens, Token};
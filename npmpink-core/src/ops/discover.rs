@@ -0,0 +1,132 @@
+use crate::ops::packages::is_publishable_package;
+use crate::walker::{self, WalkOption};
+use crate::workspace::Workspace;
+use anyhow::Result;
+use regex::bytes::Regex;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceCandidateKind {
+    /// npm workspaces root, contains multiple packages.
+    Workspaces,
+    /// Standalone publishable package.
+    Package,
+}
+
+/// A directory that could be registered as source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceCandidate {
+    /// Absolute path
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    pub kind: SourceCandidateKind,
+}
+
+impl fmt::Display for SourceCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            SourceCandidateKind::Workspaces => "workspaces",
+            SourceCandidateKind::Package => "package",
+        };
+        write!(
+            f,
+            "{}  ({}, {})",
+            self.dir.display(),
+            self.name.as_deref().unwrap_or("<unnamed>"),
+            kind
+        )
+    }
+}
+
+/// Find workspace roots and standalone publishable packages under `root`.
+///
+/// Packages that live inside a discovered workspaces root are not returned,
+/// since adding the root as source already covers them.
+pub fn discover_source_candidates(
+    root: impl AsRef<Path>,
+    max_depth: usize,
+) -> Result<Vec<SourceCandidate>> {
+    let wo = WalkOption::new(vec![Regex::new(r"package\.json$").unwrap()]).max_depth(max_depth);
+    let mut paths = walker::walk(&[root], Some(wo))?
+        .into_iter()
+        .filter(|p| !is_in_node_modules(p))
+        .collect::<Vec<PathBuf>>();
+    // parents come before children, so workspaces roots are seen first.
+    paths.sort_by_key(|p| (p.components().count(), p.clone()));
+
+    let mut candidates = Vec::<SourceCandidate>::new();
+
+    for path in paths {
+        let Some(dir) = path.parent() else {
+            continue;
+        };
+        if candidates
+            .iter()
+            .any(|c| c.kind == SourceCandidateKind::Workspaces && dir.starts_with(&c.dir))
+        {
+            continue;
+        }
+
+        let ws = Workspace::init_from_dir(dir);
+        let Ok(pkg) = ws.package_json() else {
+            continue;
+        };
+
+        let kind = if ws.is_npm_workspaces_project().unwrap_or(false) {
+            SourceCandidateKind::Workspaces
+        } else if is_publishable_package(pkg) {
+            SourceCandidateKind::Package
+        } else {
+            continue;
+        };
+
+        candidates.push(SourceCandidate {
+            name: pkg.name.clone(),
+            dir: ws.dir.clone(),
+            kind,
+        });
+    }
+
+    Ok(candidates)
+}
+
+fn is_in_node_modules(path: &Path) -> bool {
+    path.components()
+        .any(|c| matches!(c, Component::Normal(name) if name == "node_modules"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_source_candidates() {
+        let root = concat!(env!("CARGO_WORKSPACE_DIR"), "assets_");
+        let candidates = discover_source_candidates(root, 4).unwrap();
+
+        let names = candidates
+            .iter()
+            .map(|c| (c.name.clone().unwrap(), c.kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec![
+                ("dummy".to_string(), SourceCandidateKind::Package),
+                (
+                    "fixtures_npm_workspaces".to_string(),
+                    SourceCandidateKind::Workspaces
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_in_node_modules() {
+        assert!(is_in_node_modules(Path::new(
+            "/a/node_modules/b/package.json"
+        )));
+        assert!(!is_in_node_modules(Path::new("/a/b/package.json")));
+    }
+}
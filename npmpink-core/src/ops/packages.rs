@@ -1,6 +1,11 @@
-use package_json_schema::Private;
+use anyhow::{bail, Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use package_json_schema::{PackageJson, Private};
 
-use crate::{package::Package, source::Source, workspace::Workspace};
+use crate::package::{Package, PackageMeta};
+use crate::source::{Source, SourceKind};
+use crate::tarball::{is_tarball, read_package_json, tarball_paths};
+use crate::workspace::Workspace;
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
@@ -18,7 +23,14 @@ pub fn packages_paths_from_workspace(
     workspace.walk_package_jsons()
 }
 
+/// Missing or unreadable sources have no packages.
 pub fn packages_from_source(source: &Source) -> Vec<Package> {
+    if !source.is_available() {
+        return Vec::new();
+    }
+    if source.kind == SourceKind::Tarball {
+        return packages_from_tarball_source(source);
+    }
     let workspace = Workspace::init_from_dir(source.path.clone());
     workspace
         .walk_package_jsons()
@@ -29,24 +41,56 @@ pub fn packages_from_source(source: &Source) -> Vec<Package> {
             // it will fail if parse with invalid package.json, like duplicate field etc.
             ws.package_json()
                 .ok()
-                .filter(|pkg| pkg.name.is_some())
-                .filter(|pkg| {
-                    pkg.private
-                        .as_ref()
-                        .is_some_and(package_private_is_not_falsy)
-                        || pkg.private.as_ref().is_none()
-                })
+                .filter(|pkg| is_publishable_package(pkg))
                 .map(|pkg| {
                     Package::new(
                         pkg.name.clone().unwrap(),
                         ws_dir.to_str().unwrap().to_string(),
                         source.id.clone(),
                     )
+                    .with_meta(PackageMeta::from_package_json(pkg))
                 })
         })
         .collect()
 }
 
+/// Read metadata of pkgs without it from their own package.json, like
+/// packages of the lockfile, packages whose dir is gone have none.
+pub fn discover_meta(pkgs: &mut [Package]) {
+    for pkg in pkgs.iter_mut().filter(|p| p.meta.is_none()) {
+        pkg.meta = if is_tarball(&pkg.dir) {
+            read_package_json(&pkg.dir)
+                .ok()
+                .map(|json| PackageMeta::from_package_json(&json))
+        } else {
+            Workspace::init_from_dir(&pkg.dir)
+                .package_json()
+                .ok()
+                .map(PackageMeta::from_package_json)
+        };
+    }
+}
+
+/// Package dir is the tarball path.
+fn packages_from_tarball_source(source: &Source) -> Vec<Package> {
+    tarball_paths(&source.path)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| {
+            let pkg = read_package_json(&p).ok().filter(is_publishable_package)?;
+            let meta = PackageMeta::from_package_json(&pkg);
+            Some(
+                Package::new(
+                    pkg.name?,
+                    p.to_str().unwrap().to_string(),
+                    source.id.clone(),
+                )
+                .with_meta(meta),
+            )
+        })
+        .collect()
+}
+
 pub fn difference_packages<'a>(left: &'a [Package], right: &'a [Package]) -> Vec<Package> {
     let lhs = HashSet::<&Package>::from_iter(left);
     let rhs = HashSet::from_iter(right);
@@ -57,6 +101,50 @@ pub fn difference_packages<'a>(left: &'a [Package], right: &'a [Package]) -> Vec
         .collect::<Vec<Package>>()
 }
 
+/// Whether the pattern is a glob like `@acme/*` instead of a package name.
+pub fn is_glob_pattern(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Packages whose name is one of the patterns or matches one of the glob
+/// patterns, it's an error if any pattern matches nothing.
+pub fn match_packages(pkgs: Vec<Package>, patterns: &[String]) -> Result<Vec<Package>> {
+    let matchers = patterns
+        .iter()
+        .map(|pattern| {
+            GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map(|glob| glob.compile_matcher())
+                .with_context(|| format!("invalid package pattern `{}`", pattern))
+        })
+        .collect::<Result<Vec<GlobMatcher>>>()?;
+
+    let unmatched = matchers
+        .iter()
+        .filter(|m| !pkgs.iter().any(|p| m.is_match(&p.name)))
+        .map(|m| format!("`{}`", m.glob()))
+        .collect::<Vec<String>>();
+    if !unmatched.is_empty() {
+        bail!("no packages match {}", unmatched.join(", "));
+    }
+
+    Ok(pkgs
+        .into_iter()
+        .filter(|p| matchers.iter().any(|m| m.is_match(&p.name)))
+        .collect())
+}
+
+/// Named and not marked as `private`.
+pub(crate) fn is_publishable_package(pkg: &PackageJson) -> bool {
+    pkg.name.is_some()
+        && (pkg
+            .private
+            .as_ref()
+            .is_some_and(package_private_is_not_falsy)
+            || pkg.private.as_ref().is_none())
+}
+
 fn package_private_is_not_falsy(v: &Private) -> bool {
     match v {
         Private::False => true,
@@ -76,4 +164,74 @@ mod tests {
         assert!(!package_private_is_not_falsy(&package_is_private));
         assert!(!package_private_is_not_falsy(&package_private_is_bool));
     }
+
+    #[test]
+    fn test_packages_from_tarball_source() {
+        use crate::tarball::tests::create_tarball;
+        use crate::test_util::TempDir;
+
+        let root = TempDir::new("packages");
+        create_tarball(
+            &root.join("foo-1.0.0.tgz"),
+            &[("package.json", r#"{"name": "foo"}"#)],
+        );
+        create_tarball(
+            &root.join("bar-1.0.0.tgz"),
+            &[("package.json", r#"{"name": "bar"}"#)],
+        );
+        create_tarball(
+            &root.join("internal-1.0.0.tgz"),
+            &[("package.json", r#"{"name": "internal", "private": true}"#)],
+        );
+
+        let source = Source::new_tarball(root.path());
+        let names = packages_from_source(&source)
+            .into_iter()
+            .map(|p| p.name)
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["bar", "foo"]);
+    }
+
+    #[test]
+    fn test_match_packages() {
+        let pkgs = ["@acme/ui", "@acme/utils", "@acme/ui-kit/icons", "lodash"]
+            .into_iter()
+            .map(|name| Package::new(name.into(), format!("/libs/{}", name), "1".into()))
+            .collect::<Vec<Package>>();
+        let names = |patterns: &[&str]| {
+            let patterns = patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            match_packages(pkgs.clone(), &patterns)
+                .map(|pkgs| pkgs.into_iter().map(|p| p.name).collect::<Vec<String>>())
+        };
+
+        assert_eq!(names(&["lodash"]).unwrap(), vec!["lodash"]);
+        assert_eq!(
+            names(&["@acme/*", "lodash"]).unwrap(),
+            vec!["@acme/ui", "@acme/utils", "lodash"]
+        );
+        assert!(names(&["lodash", "react*"]).is_err());
+        assert!(is_glob_pattern("@acme/*"));
+        assert!(!is_glob_pattern("@acme/ui"));
+    }
+
+    #[test]
+    fn test_discover_meta() {
+        let dir = concat!(
+            env!("CARGO_WORKSPACE_DIR"),
+            "assets_/fixtures_npm_workspaces"
+        );
+        let source = Source::new(dir);
+        let mut pkgs = vec![
+            Package::new(
+                "workspace-d".into(),
+                format!("{}/nested/workspace-d", dir),
+                source.id.clone(),
+            ),
+            Package::new("gone".into(), "/libs/gone".into(), source.id.clone()),
+        ];
+
+        discover_meta(&mut pkgs);
+        assert_eq!(pkgs[0].version_label(), "0.0.2");
+        assert!(pkgs[1].meta().is_none());
+    }
 }
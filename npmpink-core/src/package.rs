@@ -1,14 +1,131 @@
+use crate::tarball::{is_tarball, read_package_json_string};
+use anyhow::Result;
+use package_json_schema::{Binary, EsNext, PackageJson, Private};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::fs;
 use std::hash::{Hash, Hasher};
+use std::path::Path;
 
-#[derive(PartialEq, Default, Serialize, Deserialize, Debug, Clone, Eq)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct Package {
     pub name: String,
     pub dir: String,
     pub source_id: String,
+    /// Loaded from package.json when the package is found, it's not saved
+    /// in the lockfile.
+    #[serde(skip)]
+    pub meta: Option<PackageMeta>,
 }
 
+/// Fields of package.json used by pickers, status and sync.
+#[derive(PartialEq, Default, Serialize, Debug, Clone)]
+pub struct PackageMeta {
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub private: bool,
+    pub main: Option<String>,
+    /// Path of `module`, the object form is ignored.
+    pub module: Option<String>,
+    /// `types` or `typings`.
+    pub types: Option<String>,
+    pub exports: Option<Value>,
+    /// Command name to script path, a single path is named after the
+    /// package name without scope like npm does.
+    pub bin: BTreeMap<String, String>,
+    pub dependencies: BTreeMap<String, String>,
+    pub optional_dependencies: BTreeMap<String, String>,
+    pub peer_dependencies: BTreeMap<String, String>,
+    pub peer_dependencies_meta: Option<Value>,
+    pub engines: BTreeMap<String, String>,
+    pub scripts: BTreeMap<String, String>,
+    pub publish_config: Option<Value>,
+}
+
+impl PackageMeta {
+    pub fn from_package_json(pkg: &PackageJson) -> Self {
+        let bin = match pkg.bin {
+            Some(Binary::Path(ref path)) => pkg
+                .name
+                .as_deref()
+                .map(|name| {
+                    let command = name.rsplit('/').next().unwrap_or(name);
+                    BTreeMap::from([(command.to_owned(), path.clone())])
+                })
+                .unwrap_or_default(),
+            Some(Binary::Object(ref bins)) => bins.clone().into_iter().collect(),
+            None => BTreeMap::new(),
+        };
+        PackageMeta {
+            version: pkg.version.clone(),
+            description: pkg.description.clone(),
+            private: matches!(pkg.private, Some(Private::True | Private::Bool(true))),
+            main: pkg.main.clone(),
+            module: match pkg.module {
+                Some(EsNext::Path(ref path)) => Some(path.clone()),
+                _ => None,
+            },
+            types: pkg.types.clone().or_else(|| pkg.typings.clone()),
+            exports: pkg
+                .exports
+                .as_ref()
+                .and_then(|e| serde_json::to_value(e).ok()),
+            bin,
+            dependencies: pkg
+                .dependencies
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            optional_dependencies: pkg
+                .optional_dependencies
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            peer_dependencies: pkg
+                .peer_dependencies
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            peer_dependencies_meta: pkg
+                .other
+                .as_ref()
+                .and_then(|other| other.get("peerDependenciesMeta").cloned()),
+            engines: pkg
+                .engines
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            scripts: pkg
+                .scripts
+                .clone()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|(name, script)| Some((name, script?)))
+                .collect(),
+            publish_config: pkg
+                .publish_config
+                .as_ref()
+                .and_then(|c| serde_json::to_value(c).ok()),
+        }
+    }
+}
+
+/// Same package if name, dir and source are the same, metadata may not be
+/// loaded.
+impl PartialEq for Package {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.dir == other.dir && self.source_id == other.source_id
+    }
+}
+
+impl Eq for Package {}
+
 impl Hash for Package {
     fn hash<H: Hasher>(&self, state: &mut H) {
         (self.name.clone() + &self.source_id).hash(state);
@@ -21,8 +138,37 @@ impl Package {
             name,
             dir,
             source_id,
+            meta: None,
         }
     }
+
+    pub fn with_meta(mut self, meta: PackageMeta) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
+    /// Metadata loaded when the package was found in it's source, none for
+    /// packages read from the lockfile.
+    pub fn meta(&self) -> Option<&PackageMeta> {
+        self.meta.as_ref()
+    }
+
+    /// Version of metadata, or `unknown` if it's not loaded.
+    pub fn version_label(&self) -> &str {
+        self.meta()
+            .and_then(|m| m.version.as_deref())
+            .unwrap_or("unknown")
+    }
+
+    /// Content of package.json, dir of tarball package is the tarball path.
+    pub fn read_manifest(&self) -> Result<String> {
+        if is_tarball(&self.dir) {
+            return read_package_json_string(&self.dir);
+        }
+        Ok(fs::read_to_string(
+            Path::new(&self.dir).join("package.json"),
+        )?)
+    }
 }
 
 // TODO: better format this package for different pickers.
@@ -39,13 +185,14 @@ impl Package {
             name: "foo".into(),
             dir: "foo/bar".into(),
             source_id: "1".into(),
+            meta: None,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Package;
+    use super::{Package, PackageJson, PackageMeta};
 
     #[test]
     fn test_package() {
@@ -60,6 +207,7 @@ mod tests {
             name: "foo".into(),
             dir: "bar".into(),
             source_id: "foo".into(),
+            meta: None,
         };
 
         assert_tokens(
@@ -83,4 +231,33 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn test_package_meta() {
+        let pkg = PackageJson::try_from(
+            r#"{
+                "name": "@acme/cli",
+                "version": "2.0.0",
+                "private": true,
+                "typings": "index.d.ts",
+                "module": {"browser": "esm/browser.js"},
+                "bin": "bin/cli.js",
+                "peerDependencies": {"react": "^18"}
+            }"#,
+        )
+        .unwrap();
+        let meta = PackageMeta::from_package_json(&pkg);
+
+        assert_eq!(meta.version.as_deref(), Some("2.0.0"));
+        assert!(meta.private);
+        assert_eq!(meta.types.as_deref(), Some("index.d.ts"));
+        assert_eq!(meta.module, None);
+        assert_eq!(meta.bin["cli"], "bin/cli.js");
+        assert!(meta.dependencies.is_empty());
+        assert_eq!(meta.peer_dependencies["react"], "^18");
+
+        // metadata doesn't make packages different.
+        let found = Package::test_new().with_meta(meta);
+        assert_eq!(found, Package::test_new());
+    }
 }
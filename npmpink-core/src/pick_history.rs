@@ -0,0 +1,161 @@
+use crate::file_lock::{load_json, update_json};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Packages picked in pickers, globally and per target, so packages picked
+/// often and recently are listed first.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct PickHistory {
+    /// Picks of all targets by package name.
+    #[serde(default)]
+    pub packages: BTreeMap<String, Frecency>,
+    #[serde(default)]
+    pub targets: BTreeMap<PathBuf, TargetPicks>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct TargetPicks {
+    #[serde(default)]
+    pub packages: BTreeMap<String, Frecency>,
+    /// Names picked the last time by command, like `add`.
+    #[serde(default)]
+    pub last: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct Frecency {
+    pub count: u64,
+    /// Unix time in seconds.
+    pub last_picked: u64,
+}
+
+impl Frecency {
+    fn bump(&mut self, now: u64) {
+        self.count += 1;
+        self.last_picked = now;
+    }
+
+    /// Count of picks weighted by how recent the last pick is.
+    pub fn score(&self, now: u64) -> u64 {
+        let weight = match now.saturating_sub(self.last_picked) {
+            age if age < HOUR => 16,
+            age if age < DAY => 8,
+            age if age < WEEK => 2,
+            _ => 1,
+        };
+        self.count * weight
+    }
+}
+
+impl PickHistory {
+    /// Load from the history file, which may not exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load_json(path)
+    }
+
+    /// Save names picked by the command in target dir to the history file,
+    /// the `ranked` ones are ranked higher next time.
+    pub fn record(
+        path: impl AsRef<Path>,
+        dir: &Path,
+        command: &str,
+        names: &[String],
+        ranked: &[String],
+    ) -> Result<()> {
+        let now = unix_now();
+        update_json(path, |history: &mut Self| {
+            let target = history.targets.entry(dir.to_path_buf()).or_default();
+            target.last.insert(command.to_owned(), names.to_vec());
+            for name in ranked {
+                target.packages.entry(name.clone()).or_default().bump(now);
+                history.packages.entry(name.clone()).or_default().bump(now);
+            }
+        })
+    }
+
+    /// Names picked the last time by the command in target dir.
+    pub fn last(&self, dir: &Path, command: &str) -> Option<&Vec<String>> {
+        self.targets.get(dir)?.last.get(command)
+    }
+
+    /// Score of package for target dir, picks in the target count more than
+    /// picks in other targets.
+    pub fn score(&self, dir: &Path, name: &str, now: u64) -> u64 {
+        let score_of =
+            |packages: &BTreeMap<String, Frecency>| packages.get(name).map_or(0, |f| f.score(now));
+        let target_score = self.targets.get(dir).map_or(0, |t| score_of(&t.packages));
+        target_score * 2 + score_of(&self.packages)
+    }
+
+    /// Sort items by score from high to low, items with same score keep
+    /// their order.
+    pub fn rank<T>(&self, dir: &Path, items: &mut [T], name: impl Fn(&T) -> &str) {
+        let now = unix_now();
+        items.sort_by_cached_key(|item| std::cmp::Reverse(self.score(dir, name(item), now)));
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_frecency_score() {
+        let now = 100 * WEEK;
+        let frecency = |count, age| Frecency {
+            count,
+            last_picked: now - age,
+        };
+        assert_eq!(frecency(1, 0).score(now), 16);
+        assert_eq!(frecency(3, 2 * HOUR).score(now), 24);
+        assert_eq!(frecency(3, 2 * DAY).score(now), 6);
+        assert_eq!(frecency(3, 2 * WEEK).score(now), 3);
+    }
+
+    #[test]
+    fn test_record_and_rank() {
+        let root = TempDir::new("pick-history");
+        let path = root.join("data/history.json");
+        let web = Path::new("/code/web");
+        let admin = Path::new("/code/admin");
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<String>>();
+
+        let add = names(&["@acme/ui"]);
+        PickHistory::record(&path, web, "add", &add, &add).unwrap();
+        let add = names(&["lodash", "@acme/ui"]);
+        PickHistory::record(&path, admin, "add", &add, &add).unwrap();
+        PickHistory::record(&path, admin, "remove", &names(&["lodash"]), &[]).unwrap();
+        // kept packages of an edit are not ranked again.
+        let edit = names(&["lodash", "@acme/ui", "react"]);
+        PickHistory::record(&path, admin, "edit", &edit, &names(&["react"])).unwrap();
+
+        let history = PickHistory::load(&path).unwrap();
+        assert_eq!(history.packages["@acme/ui"].count, 2);
+        assert_eq!(history.packages["react"].count, 1);
+        assert_eq!(history.last(admin, "edit").unwrap(), &edit);
+        assert_eq!(
+            history.last(admin, "add").unwrap(),
+            &names(&["lodash", "@acme/ui"])
+        );
+        assert_eq!(history.last(admin, "remove").unwrap(), &names(&["lodash"]));
+        assert!(history.last(web, "remove").is_none());
+
+        let mut items = ["vue", "lodash", "@acme/ui"];
+        history.rank(web, &mut items, |item| item);
+        assert_eq!(items, ["@acme/ui", "lodash", "vue"]);
+    }
+}
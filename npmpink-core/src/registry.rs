@@ -0,0 +1,345 @@
+use crate::package::Package;
+use crate::tarball::{is_tarball, pack_dir_with};
+use anyhow::Result;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha512};
+use std::cell::RefCell;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+const INSTALL_SCRIPTS: [&str; 3] = ["preinstall", "install", "postinstall"];
+
+/// Request to the read-only npm registry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegistryRequest {
+    /// `GET /<name>`, the package document of all versions.
+    Packument(String),
+    /// `GET /<name>/-/<file>.tgz`
+    Tarball(String),
+}
+
+impl RegistryRequest {
+    /// Parse the url path, scoped name can be `@scope%2fname` or `@scope/name`.
+    pub fn from_url_path(path: &str) -> Option<Self> {
+        let path = path.split('?').next().unwrap_or_default();
+        let path = percent_decode(path.trim_start_matches('/'));
+
+        if let Some((name, file)) = path.split_once("/-/") {
+            return file
+                .ends_with(".tgz")
+                .then(|| RegistryRequest::Tarball(name.to_owned()));
+        }
+        if path.is_empty() || path.starts_with('-') {
+            return None;
+        }
+        Some(RegistryRequest::Packument(path))
+    }
+}
+
+/// Serves packages of sources as npm registry, tarballs of package dirs are
+/// packed again on every package document request so it's always the latest
+/// build, the tarball request then gets the same bytes as the integrity.
+pub struct Registry {
+    packages: BTreeMap<String, Package>,
+    /// Packages skipped because an earlier source has the same name.
+    conflicts: Vec<Package>,
+    tarballs: RefCell<HashMap<String, Vec<u8>>>,
+}
+
+impl Registry {
+    /// The first package of a name is served, later ones are kept in
+    /// `conflicts`.
+    pub fn new(packages: impl IntoIterator<Item = Package>) -> Self {
+        let mut served = BTreeMap::new();
+        let mut conflicts = Vec::new();
+        for pkg in packages {
+            match served.entry(pkg.name.clone()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(pkg);
+                }
+                Entry::Occupied(_) => conflicts.push(pkg),
+            }
+        }
+
+        Registry {
+            packages: served,
+            conflicts,
+            tarballs: RefCell::default(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Package> {
+        self.packages.get(name)
+    }
+
+    pub fn conflicts(&self) -> &[Package] {
+        &self.conflicts
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    /// Package document with the only version of package in the source.
+    pub fn packument(&self, name: &str, base_url: &str) -> Result<Option<Value>> {
+        let Some(pkg) = self.packages.get(name) else {
+            return Ok(None);
+        };
+
+        let meta = pkg.meta().cloned().unwrap_or_default();
+        let version = meta.version.clone().unwrap_or("0.0.0".to_owned());
+        let tarball = self.pack(pkg)?;
+        let basename = name.rsplit('/').next().unwrap_or(name);
+
+        let mut manifest = json!({
+            "name": name,
+            "version": version,
+            "_id": format!("{}@{}", name, version),
+            "description": meta.description,
+            "main": meta.main,
+            "module": meta.module,
+            "types": meta.types,
+            "exports": meta.exports,
+            "bin": meta.bin,
+            "dependencies": meta.dependencies,
+            "optionalDependencies": meta.optional_dependencies,
+            "peerDependencies": meta.peer_dependencies,
+            "peerDependenciesMeta": meta.peer_dependencies_meta,
+            "engines": meta.engines,
+            "scripts": meta.scripts,
+            "publishConfig": meta.publish_config,
+        });
+        if INSTALL_SCRIPTS
+            .iter()
+            .any(|s| meta.scripts.contains_key(*s))
+        {
+            manifest["hasInstallScript"] = true.into();
+        }
+        self.resolve_workspace_ranges(&mut manifest);
+        // fields not in package.json are left out.
+        if let Some(fields) = manifest.as_object_mut() {
+            fields.retain(|_, v| !v.is_null() && v.as_object().is_none_or(|o| !o.is_empty()));
+        }
+        manifest["dist"] = json!({
+            "tarball": format!("{}/{}/-/{}-{}.tgz", base_url.trim_end_matches('/'), name, basename, version),
+            "shasum": sha1_smol::Sha1::from(&tarball).digest().to_string(),
+            "integrity": format!(
+                "sha512-{}",
+                base64::engine::general_purpose::STANDARD.encode(Sha512::digest(&tarball))
+            ),
+        });
+
+        let mut versions = Map::new();
+        versions.insert(version.clone(), manifest);
+
+        self.tarballs.borrow_mut().insert(name.to_owned(), tarball);
+
+        Ok(Some(json!({
+            "_id": name,
+            "name": name,
+            "dist-tags": { "latest": version },
+            "versions": versions,
+        })))
+    }
+
+    /// Tarball packed for the last package document, or packed now if the
+    /// document is not requested yet.
+    pub fn tarball(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let Some(pkg) = self.packages.get(name) else {
+            return Ok(None);
+        };
+
+        if let Some(tarball) = self.tarballs.borrow().get(name) {
+            return Ok(Some(tarball.clone()));
+        }
+        let tarball = self.pack(pkg)?;
+        self.tarballs
+            .borrow_mut()
+            .insert(name.to_owned(), tarball.clone());
+        Ok(Some(tarball))
+    }
+
+    fn pack(&self, pkg: &Package) -> Result<Vec<u8>> {
+        if is_tarball(&pkg.dir) {
+            return Ok(fs::read(&pkg.dir)?);
+        }
+        pack_dir_with(&pkg.dir, |manifest| self.resolve_workspace_ranges(manifest))
+    }
+
+    /// `workspace:` ranges of dependencies to versions of the served
+    /// packages, like pnpm and yarn do on publish, npm doesn't know them.
+    fn resolve_workspace_ranges(&self, manifest: &mut Value) {
+        for field in DEPENDENCY_FIELDS {
+            let Some(deps) = manifest.get_mut(field).and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for (name, range) in deps.iter_mut() {
+                let Some(spec) = range.as_str().and_then(|r| r.strip_prefix("workspace:")) else {
+                    continue;
+                };
+                let version = self
+                    .packages
+                    .get(name)
+                    .and_then(|p| p.meta())
+                    .and_then(|m| m.version.as_deref());
+                *range = workspace_range(spec, version).into();
+            }
+        }
+    }
+}
+
+/// `workspace:^` to `^1.2.0` if the package is `1.2.0`, other ranges like
+/// `workspace:^1.0.0` are kept without the protocol.
+fn workspace_range(spec: &str, version: Option<&str>) -> String {
+    match (spec, version) {
+        ("*", Some(version)) => version.to_owned(),
+        ("^" | "~", Some(version)) => format!("{}{}", spec, version),
+        ("*" | "^" | "~", None) => "*".to_owned(),
+        _ => spec.to_owned(),
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PackageMeta;
+    use crate::tarball::read_package_json_string;
+    use crate::test_util::TempDir;
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn test_registry_request_from_url_path() {
+        assert_eq!(
+            RegistryRequest::from_url_path("/foo"),
+            Some(RegistryRequest::Packument("foo".into()))
+        );
+        assert_eq!(
+            RegistryRequest::from_url_path("/@acme%2fui"),
+            Some(RegistryRequest::Packument("@acme/ui".into()))
+        );
+        assert_eq!(
+            RegistryRequest::from_url_path("/@acme/ui/-/ui-1.0.0.tgz"),
+            Some(RegistryRequest::Tarball("@acme/ui".into()))
+        );
+        assert_eq!(RegistryRequest::from_url_path("/-/ping"), None);
+        assert_eq!(RegistryRequest::from_url_path("/"), None);
+    }
+
+    #[test]
+    fn test_registry_packument() {
+        let dir = concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/dummy");
+        let registry = Registry::new([Package::new("dummy".into(), dir.into(), "1".into())
+            .with_meta(PackageMeta::from_package_json(
+                Workspace::init_from_dir(dir).package_json().unwrap(),
+            ))]);
+
+        let packument = registry
+            .packument("dummy", "http://localhost:4873/")
+            .unwrap()
+            .unwrap();
+        assert_eq!(packument["dist-tags"]["latest"], "1.0.0");
+
+        let version = &packument["versions"]["1.0.0"];
+        assert_eq!(
+            version["dist"]["tarball"],
+            "http://localhost:4873/dummy/-/dummy-1.0.0.tgz"
+        );
+        assert_eq!(version["dependencies"]["dummy"], "1.0.1");
+        assert!(registry.packument("unknown", "").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_registry_workspace_ranges() {
+        let root = TempDir::new("registry-workspace");
+        let package = |name: &str, manifest: &str| {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("package.json"), manifest).unwrap();
+            Package::new(name.into(), dir.to_string_lossy().into(), "1".into()).with_meta(
+                PackageMeta::from_package_json(
+                    Workspace::init_from_dir(&dir).package_json().unwrap(),
+                ),
+            )
+        };
+        let registry = Registry::new([
+            package("ui", r#"{"name": "ui", "version": "1.2.0"}"#),
+            package(
+                "app",
+                r#"{
+                    "name": "app",
+                    "version": "1.0.0",
+                    "dependencies": {"ui": "workspace:^", "lodash": "^4"},
+                    "peerDependencies": {"react": "^18"},
+                    "peerDependenciesMeta": {"react": {"optional": true}},
+                    "engines": {"node": ">=18"},
+                    "scripts": {"postinstall": "node setup.js"}
+                }"#,
+            ),
+        ]);
+
+        let packument = registry.packument("app", "").unwrap().unwrap();
+        let version = &packument["versions"]["1.0.0"];
+        assert_eq!(version["dependencies"]["ui"], "^1.2.0");
+        assert_eq!(version["dependencies"]["lodash"], "^4");
+        assert_eq!(version["peerDependenciesMeta"]["react"]["optional"], true);
+        assert_eq!(version["engines"]["node"], ">=18");
+        assert_eq!(version["hasInstallScript"], true);
+
+        let tarball = root.join("app.tgz");
+        fs::write(&tarball, registry.tarball("app").unwrap().unwrap()).unwrap();
+        let manifest: Value =
+            serde_json::from_str(&read_package_json_string(&tarball).unwrap()).unwrap();
+        assert_eq!(manifest["dependencies"]["ui"], "^1.2.0");
+
+        assert_eq!(workspace_range("*", Some("1.2.0")), "1.2.0");
+        assert_eq!(workspace_range("~", Some("1.2.0")), "~1.2.0");
+        assert_eq!(workspace_range("^", None), "*");
+        assert_eq!(workspace_range("^1.0.0", Some("1.2.0")), "^1.0.0");
+    }
+
+    #[test]
+    fn test_registry_conflicts() {
+        let dir = concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/dummy");
+        let registry = Registry::new([
+            Package::new("dummy".into(), dir.into(), "1".into()),
+            Package::new("dummy".into(), dir.into(), "2".into()),
+        ]);
+
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.get("dummy").unwrap().source_id, "1");
+        assert_eq!(registry.conflicts().len(), 1);
+        assert_eq!(registry.conflicts()[0].source_id, "2");
+    }
+}
@@ -1,12 +1,37 @@
+use crate::git::GitSource;
+use crate::tarball::tarball_paths;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    /// Dir of package or npm workspaces.
+    #[default]
+    Dir,
+    /// Tarball file or dir of tarballs.
+    Tarball,
+}
+
+impl SourceKind {
+    fn is_dir(&self) -> bool {
+        *self == SourceKind::Dir
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Source {
     /// Absolute path
     pub path: PathBuf,
     pub id: String,
+    #[serde(default, skip_serializing_if = "SourceKind::is_dir")]
+    pub kind: SourceKind,
+    /// Set if the path is a worktree checked out by npmpink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSource>,
 }
 
 impl Hash for Source {
@@ -15,12 +40,37 @@ impl Hash for Source {
     }
 }
 
-fn hash_path_string(str: String) -> String {
+pub(crate) fn hash_path_string(str: String) -> String {
     let mut hasher = DefaultHasher::new();
     str.hash(&mut hasher);
     hasher.finish().to_string()
 }
 
+/// Whether the source dir is still usable.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceHealth {
+    Ok,
+    /// Source dir does not exist anymore, maybe moved or deleted.
+    Missing,
+    /// Source dir exists but does not contain package.json.
+    NoPackageJson,
+    /// Source dir or it's package.json can not be read.
+    Unreadable,
+}
+
+impl fmt::Display for SourceHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            SourceHealth::Ok => "ok",
+            SourceHealth::Missing => "missing",
+            SourceHealth::NoPackageJson => "no package.json",
+            SourceHealth::Unreadable => "unreadable",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
 impl Source {
     pub fn new<P>(path: P) -> Self
     where
@@ -33,8 +83,77 @@ impl Source {
         Source {
             path: pathbuf.clone(),
             id: hash_path_string(path.as_ref().to_string_lossy().into()),
+            kind: SourceKind::Dir,
+            git: None,
+        }
+    }
+
+    /// Source of tarball file or dir of tarballs.
+    pub fn new_tarball<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Source {
+            kind: SourceKind::Tarball,
+            ..Source::new(path)
+        }
+    }
+
+    /// Source of worktree materialized from git repository.
+    pub fn new_git<P>(path: P, git: GitSource) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Source {
+            git: Some(git),
+            ..Source::new(path)
+        }
+    }
+
+    /// Same source at another path, the id follows the path.
+    pub fn moved_to<P>(&self, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Source {
+            kind: self.kind,
+            git: self.git.clone(),
+            ..Source::new(path)
         }
     }
+
+    pub fn health(&self) -> SourceHealth {
+        match self.path.try_exists() {
+            Ok(true) => {}
+            Ok(false) => return SourceHealth::Missing,
+            Err(_) => return SourceHealth::Unreadable,
+        }
+        if self.kind == SourceKind::Tarball {
+            return match tarball_paths(&self.path) {
+                Ok(_) => SourceHealth::Ok,
+                Err(_) => SourceHealth::Unreadable,
+            };
+        }
+        if fs::read_dir(&self.path).is_err() {
+            return SourceHealth::Unreadable;
+        }
+
+        let pkg_path = self.path.join("package.json");
+        match pkg_path.try_exists() {
+            Ok(true) => {}
+            Ok(false) => return SourceHealth::NoPackageJson,
+            Err(_) => return SourceHealth::Unreadable,
+        }
+        if fs::read_to_string(pkg_path).is_err() {
+            return SourceHealth::Unreadable;
+        }
+
+        SourceHealth::Ok
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.health() == SourceHealth::Ok
+    }
 }
 
 #[cfg(test)]
@@ -58,5 +177,52 @@ mod tests {
 
         let from_string = serde_json::from_str::<Source>(&source_json_value).unwrap();
         assert_eq!(from_string.id, source_id);
+        assert!(!source_json_value.contains("git"));
+        assert!(!source_json_value.contains("kind"));
+    }
+
+    #[test]
+    fn test_source_moved_to() {
+        let source = Source::new_git(PATHSTR, GitSource::new("/tmp/repo", "main"));
+        let moved = source.moved_to("/tmp/moved");
+
+        assert_eq!(moved.id, hash_path_string("/tmp/moved".to_string()));
+        assert_eq!(moved.git, source.git);
+        assert_eq!(
+            Source::new_tarball(PATHSTR).moved_to("/tmp/moved").kind,
+            SourceKind::Tarball
+        );
+    }
+
+    #[test]
+    fn test_git_source_json() {
+        let source = Source::new_git(PATHSTR, GitSource::new("/tmp/repo", "main"));
+        let source_json_value = serde_json::to_string_pretty(&source).unwrap();
+
+        let from_string = serde_json::from_str::<Source>(&source_json_value).unwrap();
+        assert_eq!(from_string, source);
+    }
+
+    #[test]
+    fn test_tarball_source_json() {
+        let source = Source::new_tarball(PATHSTR);
+        let source_json_value = serde_json::to_string(&source).unwrap();
+        assert!(source_json_value.contains(r#""kind":"tarball""#));
+
+        let from_string = serde_json::from_str::<Source>(&source_json_value).unwrap();
+        assert_eq!(from_string.kind, SourceKind::Tarball);
+    }
+
+    #[test]
+    fn test_source_health() {
+        let dummy = Source::new(concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/dummy"));
+        assert_eq!(dummy.health(), SourceHealth::Ok);
+
+        let home = Source::new(concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/home"));
+        assert_eq!(home.health(), SourceHealth::NoPackageJson);
+
+        let missing = Source::new(concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/not-exists"));
+        assert_eq!(missing.health(), SourceHealth::Missing);
+        assert!(!missing.is_available());
     }
 }
@@ -0,0 +1,381 @@
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use ignore::WalkBuilder;
+use package_json_schema::PackageJson;
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, Builder, EntryType, Header};
+
+/// `npm pack` output, like `foo-1.0.0.tgz`.
+pub fn is_tarball(path: impl AsRef<Path>) -> bool {
+    let name = path.as_ref().to_string_lossy();
+    path.as_ref().is_file() && (name.ends_with(".tgz") || name.ends_with(".tar.gz"))
+}
+
+/// The path itself if it's a tarball, or tarballs directly under the dir.
+pub fn tarball_paths(path: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let path = path.as_ref();
+    if is_tarball(path) {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| is_tarball(p))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Read the package.json inside tarball without extracting it.
+pub fn read_package_json(tarball: impl AsRef<Path>) -> Result<PackageJson> {
+    let content = read_package_json_string(tarball)?;
+    PackageJson::try_from(content).map_err(anyhow::Error::msg)
+}
+
+pub fn read_package_json_string(tarball: impl AsRef<Path>) -> Result<String> {
+    let tarball = tarball.as_ref();
+    let mut archive = open_archive(tarball)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !is_root_package_json(&entry.path()?) {
+            continue;
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        return Ok(content);
+    }
+
+    bail!("no package.json in {}", tarball.display())
+}
+
+/// Pack the package dir like `npm pack`, returns the gzipped tarball.
+///
+/// Respects `.npmignore` and `.gitignore`, or only the `files` field of
+/// package.json if it's set, `node_modules` and `.git` are always excluded.
+pub fn pack_dir(dir: impl AsRef<Path>) -> Result<Vec<u8>> {
+    pack_dir_with(dir, |_| {})
+}
+
+/// Same as `pack_dir`, the packed package.json is changed by `edit` first.
+pub fn pack_dir_with(
+    dir: impl AsRef<Path>,
+    edit: impl FnOnce(&mut serde_json::Value),
+) -> Result<Vec<u8>> {
+    let dir = dir.as_ref();
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("package.json"))?)?;
+    let mut edited = manifest.clone();
+    edit(&mut edited);
+    let files_field = manifest["files"].as_array().map(|files| {
+        files
+            .iter()
+            .filter_map(|f| f.as_str())
+            .map(files_field_prefix)
+            .collect::<Vec<String>>()
+    });
+
+    let mut walker = WalkBuilder::new(dir);
+    walker.hidden(false).require_git(false);
+    if files_field.is_some() {
+        // `files` overrides the ignore files like npm, build output listed
+        // there is usually gitignored.
+        walker
+            .git_ignore(false)
+            .git_global(false)
+            .git_exclude(false)
+            .ignore(false);
+    } else {
+        walker.add_custom_ignore_filename(".npmignore");
+    }
+
+    let mut paths = walker
+        .filter_entry(|e| e.file_name() != "node_modules" && e.file_name() != ".git")
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|e| e.path().strip_prefix(dir).ok().map(Path::to_path_buf))
+        .filter(|p| match files_field {
+            Some(ref prefixes) => is_always_packed(p) || prefixes.iter().any(|f| p.starts_with(f)),
+            None => true,
+        })
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for path in paths {
+        let content = if path == Path::new("package.json") && edited != manifest {
+            serde_json::to_vec_pretty(&edited)?
+        } else {
+            fs::read(dir.join(&path))?
+        };
+        let executable = is_executable(&dir.join(&path));
+
+        let mut header = Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(if executable { 0o755 } else { 0o644 });
+        // same fixed time as npm, so packing is reproducible.
+        header.set_mtime(499162500);
+        header.set_cksum();
+        builder.append_data(
+            &mut header,
+            Path::new("package").join(&path),
+            content.as_slice(),
+        )?;
+    }
+
+    Ok(builder.into_inner()?.finish()?)
+}
+
+/// `dist/*.js` or `./lib/` to path prefix.
+fn files_field_prefix(pattern: &str) -> String {
+    let pattern = pattern.trim_start_matches("./");
+    let end = pattern.find(['*', '?', '[', '{']).unwrap_or(pattern.len());
+    pattern[..end].trim_end_matches('/').to_owned()
+}
+
+fn is_always_packed(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    path.components().count() == 1
+        && (name == "package.json" || name.starts_with("readme") || name.starts_with("license"))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+/// Extract the package content into dest, the top dir of archive (usually
+/// `package/`) is stripped. Existing dest is replaced.
+pub fn extract_package(tarball: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<()> {
+    unpack_package(open_archive(tarball.as_ref())?, dest.as_ref())
+}
+
+/// Copy the files that would be published of package dir into dest,
+/// existing dest is replaced.
+pub fn copy_package(dir: impl AsRef<Path>, dest: impl AsRef<Path>) -> Result<()> {
+    let packed = pack_dir(dir)?;
+    unpack_package(
+        Archive::new(GzDecoder::new(packed.as_slice())),
+        dest.as_ref(),
+    )
+}
+
+fn unpack_package<R: Read>(mut archive: Archive<R>, dest: &Path) -> Result<()> {
+    if fs::symlink_metadata(dest).is_ok() {
+        if dest.is_dir() && !dest.is_symlink() {
+            fs::remove_dir_all(dest)?;
+        } else {
+            fs::remove_file(dest)?;
+        }
+    }
+    fs::create_dir_all(dest)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let stripped = path.components().skip(1).collect::<PathBuf>();
+        if stripped.as_os_str().is_empty() {
+            continue;
+        }
+        if stripped
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            bail!("invalid path {} in tarball", path.display());
+        }
+        // links could point outside of dest, and later entries would be
+        // written through them.
+        if matches!(
+            entry.header().entry_type(),
+            EntryType::Symlink | EntryType::Link
+        ) {
+            bail!("link {} in tarball is not supported", path.display());
+        }
+
+        let out = dest.join(stripped);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        entry.unpack(&out)?;
+    }
+
+    Ok(())
+}
+
+fn open_archive(tarball: &Path) -> Result<Archive<GzDecoder<fs::File>>> {
+    let file =
+        fs::File::open(tarball).with_context(|| format!("failed to open {}", tarball.display()))?;
+    Ok(Archive::new(GzDecoder::new(file)))
+}
+
+/// `package/package.json`, the top dir name may differ.
+fn is_root_package_json(path: &Path) -> bool {
+    let components = path.components().collect::<Vec<_>>();
+    components.len() == 2 && components[1].as_os_str() == "package.json"
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Create tarball like `npm pack` does.
+    pub(crate) fn create_tarball(path: &Path, files: &[(&str, &str)]) {
+        let file = fs::File::create(path).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("package/{}", name), content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_read_and_extract_tarball() {
+        let root = TempDir::new("tarball");
+        let tarball = root.join("foo-1.0.0.tgz");
+        create_tarball(
+            &tarball,
+            &[
+                ("package.json", r#"{"name": "foo", "version": "1.0.0"}"#),
+                ("lib/index.js", "module.exports = 1;"),
+            ],
+        );
+
+        assert_eq!(tarball_paths(root.path()).unwrap(), vec![tarball.clone()]);
+
+        let pkg = read_package_json(&tarball).unwrap();
+        assert_eq!(pkg.name.as_deref(), Some("foo"));
+
+        let dest = root.join("node_modules/foo");
+        extract_package(&tarball, &dest).unwrap();
+        assert!(dest.join("package.json").is_file());
+        assert!(dest.join("lib/index.js").is_file());
+
+        let copy_dest = root.join("node_modules/dummy");
+        copy_package(
+            concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/dummy"),
+            &copy_dest,
+        )
+        .unwrap();
+        assert!(copy_dest.join("package.json").is_file());
+    }
+
+    #[test]
+    fn test_extract_tarball_with_link() {
+        let root = TempDir::new("tarball-link");
+        let outside = root.join("outside");
+        fs::create_dir_all(&outside).unwrap();
+
+        let tarball = root.join("evil-1.0.0.tgz");
+        let file = fs::File::create(&tarball).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        let mut link = tar::Header::new_gnu();
+        link.set_entry_type(EntryType::Symlink);
+        link.set_size(0);
+        builder
+            .append_link(&mut link, "package/lib", &outside)
+            .unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "package/lib/x", "{}".as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert!(extract_package(&tarball, root.join("node_modules/evil")).is_err());
+        assert!(!outside.join("x").exists());
+    }
+
+    #[test]
+    fn test_pack_dir() {
+        let root = concat!(
+            env!("CARGO_WORKSPACE_DIR"),
+            "assets_/fixtures_npm_workspaces"
+        );
+        let temp = TempDir::new("pack");
+        let tarball = temp.join("fixtures_npm_workspaces.tgz");
+        fs::write(&tarball, pack_dir(root).unwrap()).unwrap();
+
+        let pkg = read_package_json(&tarball).unwrap();
+        assert_eq!(pkg.name.as_deref(), Some("fixtures_npm_workspaces"));
+    }
+
+    #[test]
+    fn test_pack_dir_with_ignored_files() {
+        let root = TempDir::new("pack-ignored");
+        fs::create_dir_all(root.join("dist")).unwrap();
+        fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        fs::write(root.join("dist/index.js"), "module.exports = 1;").unwrap();
+        fs::write(root.join("index.ts"), "export default 1;").unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "foo", "files": ["dist"]}"#,
+        )
+        .unwrap();
+
+        let packed = pack_dir(root.path()).unwrap();
+        let mut names = Archive::new(GzDecoder::new(packed.as_slice()))
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        names.sort();
+        assert_eq!(names, vec!["package/dist/index.js", "package/package.json"]);
+    }
+
+    #[test]
+    fn test_copy_package_with_ignored_build_dir() {
+        let root = TempDir::new("copy-ignored");
+        let pkg = root.join("libs/foo");
+        fs::create_dir_all(pkg.join("dist")).unwrap();
+        fs::write(pkg.join(".gitignore"), "dist\n").unwrap();
+        fs::write(pkg.join("dist/index.js"), "module.exports = 1;").unwrap();
+        fs::write(
+            pkg.join("package.json"),
+            r#"{"name": "foo", "main": "dist/index.js", "files": ["dist"]}"#,
+        )
+        .unwrap();
+
+        let dest = root.join("web/node_modules/foo");
+        copy_package(&pkg, &dest).unwrap();
+        assert!(dest.join("dist/index.js").is_file());
+        assert!(!dest.join(".gitignore").exists());
+    }
+
+    #[test]
+    fn test_files_field_prefix() {
+        assert_eq!(files_field_prefix("./dist/"), "dist");
+        assert_eq!(files_field_prefix("lib/*.js"), "lib");
+        assert_eq!(files_field_prefix("index.js"), "index.js");
+    }
+
+    #[test]
+    fn test_is_root_package_json() {
+        assert!(is_root_package_json(Path::new("package/package.json")));
+        assert!(!is_root_package_json(Path::new("package/lib/package.json")));
+    }
+}
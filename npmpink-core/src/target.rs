@@ -1,3 +1,4 @@
+use crate::file_lock::{write_atomic, FileLock};
 use crate::lockfile::LockfileContent;
 use crate::package::Package;
 use crate::workspace::Workspace;
@@ -37,12 +38,31 @@ impl<'s> Target {
         Ok(lockfile.borrow())
     }
 
+    /// Write the lockfile as is, prefer `update_lockfile` to change it.
     pub fn flush_lockfile(&self) -> Result<()> {
         let lockfile_path = self.lockfile_path().context("failed to flush lockfile")?;
+        let _lock = FileLock::acquire(&lockfile_path)?;
         let lockfile = self.lockfile().context("failed to get lockfile")?;
-        let content = lockfile.to_json_string()?;
 
-        fs::write(lockfile_path, content.as_bytes()).map_err(anyhow::Error::msg)
+        write_atomic(lockfile_path, lockfile.to_json_string()?)
+    }
+
+    /// Reload the lockfile while holding its lock, change it with `f` and
+    /// write it back if changed, the lockfile of `self` is reloaded too.
+    pub fn update_lockfile<T>(&self, f: impl FnOnce(&mut LockfileContent) -> T) -> Result<T> {
+        let lockfile_path = self.lockfile_path().context("failed to update lockfile")?;
+        let _lock = FileLock::acquire(&lockfile_path)?;
+
+        let mut lockfile = self.lockfile_mut()?;
+        *lockfile = self.load_lockfile_or_default()?;
+        let before = lockfile.to_json_string()?;
+        let result = f(&mut lockfile);
+
+        let content = lockfile.to_json_string()?;
+        if content != before {
+            write_atomic(lockfile_path, content)?;
+        }
+        Ok(result)
     }
 
     fn load_lockfile_or_default(&self) -> Result<LockfileContent> {
@@ -0,0 +1,85 @@
+use crate::file_lock::{load_json, update_json};
+use crate::target::Target;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Dirs of targets that packages are added to or synced in, so they can
+/// be found again from anywhere.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct TargetRegistry {
+    pub targets: BTreeSet<PathBuf>,
+}
+
+impl TargetRegistry {
+    /// Load from the registry file, which may not exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        load_json(path)
+    }
+
+    /// Add the target dir to the registry file.
+    pub fn register(path: impl AsRef<Path>, dir: impl AsRef<Path>) -> Result<()> {
+        update_json(path, |registry: &mut Self| {
+            registry.targets.insert(dir.as_ref().to_path_buf());
+        })
+    }
+
+    /// Targets that still exist, the others are removed or moved.
+    pub fn existing_targets(&self) -> impl Iterator<Item = &PathBuf> {
+        self.targets.iter().filter(|dir| dir.is_dir())
+    }
+
+    /// Targets that have the package in their lockfile, targets with a
+    /// broken lockfile are kept so the caller can report them.
+    pub fn targets_with_package(&self, name: &str) -> Vec<PathBuf> {
+        self.existing_targets()
+            .filter(|dir| {
+                Target::init_from_dir(dir)
+                    .lockfile()
+                    .map_or(true, |lockfile| lockfile.packages.contains_key(name))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::LockfileContent;
+    use crate::package::Package;
+    use crate::test_util::TempDir;
+    use std::fs;
+
+    #[test]
+    fn test_register_and_find_targets() {
+        let root = TempDir::new("target-registry");
+        let registry_path = root.join("data/targets.json");
+        let web = root.join("web");
+        let admin = root.join("admin");
+        fs::create_dir_all(&web).unwrap();
+        fs::create_dir_all(&admin).unwrap();
+
+        let mut lockfile = LockfileContent::new();
+        lockfile.add_package(
+            "@acme/ui".into(),
+            Package::new("@acme/ui".into(), "/libs/ui".into(), "1".into()),
+        );
+        fs::write(web.join("npmpink.lock"), lockfile.to_json_string().unwrap()).unwrap();
+
+        TargetRegistry::register(&registry_path, &web).unwrap();
+        TargetRegistry::register(&registry_path, &admin).unwrap();
+        TargetRegistry::register(&registry_path, &web).unwrap();
+        TargetRegistry::register(&registry_path, root.join("removed")).unwrap();
+
+        let registry = TargetRegistry::load(&registry_path).unwrap();
+        assert_eq!(registry.targets.len(), 3);
+        assert_eq!(registry.existing_targets().count(), 2);
+        assert_eq!(registry.targets_with_package("@acme/ui"), vec![web.clone()]);
+        assert!(registry.targets_with_package("lodash").is_empty());
+
+        fs::write(admin.join("npmpink.lock"), "{").unwrap();
+        assert_eq!(registry.targets_with_package("lodash"), vec![admin]);
+    }
+}
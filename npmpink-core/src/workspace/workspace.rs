@@ -12,11 +12,17 @@ pub struct Workspace {
 }
 
 impl Workspace {
+    /// Dir is canonicalized if it exists, dirs that are deleted are kept
+    /// absolute so they still match the registered paths.
     pub fn init_from_dir(path: impl AsRef<Path>) -> Self {
-        let path = path.as_ref().to_path_buf().canonicalize().unwrap();
+        let path = path.as_ref();
+        let dir = path
+            .canonicalize()
+            .or_else(|_| std::path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf());
 
         Workspace {
-            dir: path,
+            dir,
             package_json: LazyCell::new(),
         }
     }
@@ -46,7 +52,7 @@ impl Workspace {
         }
     }
 
-    fn is_npm_workspaces_project(&self) -> Result<bool> {
+    pub fn is_npm_workspaces_project(&self) -> Result<bool> {
         let pkg = self.package_json()?;
         match pkg.workspaces {
             Some(Workspaces::List(ref list)) => Ok(!list.is_empty()),
@@ -89,4 +95,13 @@ mod tests {
         let dir = wk.absolute_dir();
         assert!(dir.is_ok());
     }
+
+    #[test]
+    fn test_workspace_missing_dir() {
+        let pkg_path = concat!(env!("CARGO_WORKSPACE_DIR"), "assets_/removed/");
+        let wk = Workspace::init_from_dir(pkg_path);
+
+        assert!(!wk.has_package_json());
+        assert!(wk.absolute_dir().unwrap().ends_with("assets_/removed"));
+    }
 }
@@ -0,0 +1,606 @@
+use crate::color::Color;
+use crate::shell::{shell, LogLevel, LogLine};
+use crate::terminal::{fg, TerminalGuard};
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use npmpink_core::item_display::{PackageItemDisplay, PackageStatus, SourceItemDisplay};
+use npmpink_core::item_formatter::PackageColumns;
+use npmpink_core::source::SourceHealth;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{Frame, Terminal};
+use std::io::{self, Stderr};
+use std::time::Duration;
+
+/// How often the sync status of locked packages is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// Lines kept in the log pane.
+const LOG_LIMIT: usize = 500;
+
+/// Data and actions of the dashboard, implemented by the cli.
+pub trait DashboardActions {
+    /// Dir of the target.
+    fn target(&self) -> String;
+    fn sources(&mut self) -> Result<Vec<SourceItemDisplay>>;
+    /// Packages of all sources, the scan of sources may be cached.
+    fn packages(&mut self) -> Result<Vec<PackageItemDisplay>>;
+    /// Scan sources again on the next `packages`, when reloaded by key.
+    fn rescan(&mut self);
+    /// Packages in the lockfile of target, with their sync status.
+    fn locked(&mut self) -> Result<Vec<PackageItemDisplay>>;
+    fn add(&mut self, pkg: &PackageItemDisplay) -> Result<()>;
+    fn remove(&mut self, pkg: &PackageItemDisplay) -> Result<()>;
+    fn sync(&mut self) -> Result<()>;
+    /// Remove the package from `node_modules` of target, it stays in the
+    /// lockfile.
+    fn unsync(&mut self, pkg: &PackageItemDisplay) -> Result<()>;
+}
+
+/// Run the dashboard until it's quit, messages printed with `Shell` are
+/// shown in the log pane meanwhile.
+pub fn run_dashboard(actions: &mut dyn DashboardActions) -> Result<()> {
+    shell()?.start_log();
+    let result = DashboardState::new(actions).and_then(|mut state| {
+        let _guard = TerminalGuard::enter()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+        state.run(&mut terminal, actions)
+    });
+    shell()?.stop_log();
+    result
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Pane {
+    Sources,
+    #[default]
+    Packages,
+    Locked,
+    Log,
+}
+
+impl Pane {
+    const ALL: [Pane; 4] = [Pane::Sources, Pane::Packages, Pane::Locked, Pane::Log];
+
+    fn next(self, delta: isize) -> Pane {
+        let index = Pane::ALL
+            .iter()
+            .position(|p| *p == self)
+            .unwrap_or_default();
+        let len = Pane::ALL.len() as isize;
+        Pane::ALL[(index as isize + delta).rem_euclid(len) as usize]
+    }
+}
+
+/// Work run by a key, which may take a while like sync.
+enum Task {
+    Add(PackageItemDisplay),
+    Remove(PackageItemDisplay),
+    Sync,
+    Unsync(PackageItemDisplay),
+    Reload,
+}
+
+impl Task {
+    /// Shown while the task runs.
+    fn describe(&self) -> String {
+        match self {
+            Task::Add(pkg) => format!("adding {}", pkg.title),
+            Task::Remove(pkg) => format!("removing {}", pkg.title),
+            Task::Sync => "syncing".to_owned(),
+            Task::Unsync(pkg) => format!("unsyncing {}", pkg.title),
+            Task::Reload => "reloading".to_owned(),
+        }
+    }
+}
+
+enum Action {
+    Continue,
+    Run(Task),
+    Quit,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    target: String,
+    sources: Vec<SourceItemDisplay>,
+    packages: Vec<PackageItemDisplay>,
+    locked: Vec<PackageItemDisplay>,
+    focus: Pane,
+    /// Cursor of sources, the first row is all sources.
+    source_list: ListState,
+    package_list: ListState,
+    locked_list: ListState,
+    log: Vec<LogLine>,
+    /// Lines scrolled up from the end of log.
+    log_scroll: usize,
+    /// Task running, drawn before it blocks the loop.
+    busy: Option<String>,
+    /// Why the sources can't be loaded, like an invalid config.
+    error: Option<String>,
+}
+
+impl DashboardState {
+    fn new(actions: &mut dyn DashboardActions) -> Result<Self> {
+        let mut state = DashboardState {
+            target: actions.target(),
+            ..Default::default()
+        };
+        state.source_list.select(Some(0));
+        state.reload(actions);
+        state.collect_log()?;
+        Ok(state)
+    }
+
+    fn run(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+        actions: &mut dyn DashboardActions,
+    ) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            if !event::poll(REFRESH_INTERVAL)? {
+                self.refresh_locked(actions);
+                self.collect_log()?;
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match self.handle_key(key) {
+                Action::Continue => {}
+                Action::Quit => return Ok(()),
+                Action::Run(task) => {
+                    self.busy = Some(task.describe());
+                    terminal.draw(|frame| self.render(frame))?;
+                    self.execute(task, actions);
+                    self.busy = None;
+                    self.collect_log()?;
+                }
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Char('c') if ctrl => return Action::Quit,
+            KeyCode::Tab => self.focus = self.focus.next(1),
+            KeyCode::BackTab => self.focus = self.focus.next(-1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-10),
+            KeyCode::PageDown => self.move_cursor(10),
+            KeyCode::Char('s') => return Action::Run(Task::Sync),
+            KeyCode::Char('r') => return Action::Run(Task::Reload),
+            KeyCode::Char('a') | KeyCode::Enter if self.focus == Pane::Packages => {
+                if let Some(pkg) = self.current_package() {
+                    return Action::Run(Task::Add(pkg.clone()));
+                }
+            }
+            KeyCode::Char('d') if self.focus == Pane::Locked => {
+                if let Some(pkg) = self.current_locked() {
+                    return Action::Run(Task::Remove(pkg.clone()));
+                }
+            }
+            KeyCode::Char('u') if self.focus == Pane::Locked => {
+                if let Some(pkg) = self.current_locked() {
+                    return Action::Run(Task::Unsync(pkg.clone()));
+                }
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Run the task, errors are logged so the dashboard keeps running.
+    fn execute(&mut self, task: Task, actions: &mut dyn DashboardActions) {
+        let result = match task {
+            Task::Add(ref pkg) => actions.add(pkg),
+            Task::Remove(ref pkg) => actions.remove(pkg),
+            Task::Sync => actions.sync(),
+            Task::Unsync(ref pkg) => actions.unsync(pkg),
+            Task::Reload => {
+                actions.rescan();
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            // messages printed before the error come first.
+            let _ = self.collect_log();
+            self.log_error(e);
+        }
+        self.reload(actions);
+    }
+
+    fn reload(&mut self, actions: &mut dyn DashboardActions) {
+        match actions.sources() {
+            Ok(sources) => {
+                self.sources = sources;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+        match actions.packages() {
+            Ok(packages) => self.packages = packages,
+            Err(e) => self.log_error(e),
+        }
+        self.refresh_locked(actions);
+        clamp(&mut self.source_list, self.sources.len() + 1);
+        let len = self.visible_packages().len();
+        clamp(&mut self.package_list, len);
+    }
+
+    fn refresh_locked(&mut self, actions: &mut dyn DashboardActions) {
+        match actions.locked() {
+            Ok(locked) => self.locked = locked,
+            Err(e) => self.log_error(e),
+        }
+        clamp(&mut self.locked_list, self.locked.len());
+    }
+
+    /// Move messages of `Shell` to the log pane.
+    fn collect_log(&mut self) -> Result<()> {
+        let lines = shell()?.take_log();
+        self.push_log(lines);
+        Ok(())
+    }
+
+    fn log_error(&mut self, e: anyhow::Error) {
+        self.push_log([LogLine {
+            level: LogLevel::Error,
+            message: format!("{:#}", e),
+        }]);
+    }
+
+    fn push_log(&mut self, lines: impl IntoIterator<Item = LogLine>) {
+        self.log.extend(lines);
+        let overflow = self.log.len().saturating_sub(LOG_LIMIT);
+        self.log.drain(..overflow);
+    }
+
+    /// Packages of the source under cursor, or of all sources.
+    fn visible_packages(&self) -> Vec<&PackageItemDisplay> {
+        let source = self
+            .source_list
+            .selected()
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| self.sources.get(i));
+        self.packages
+            .iter()
+            .filter(|pkg| source.is_none_or(|s| s.id == pkg.source_id))
+            .collect()
+    }
+
+    fn current_package(&self) -> Option<&PackageItemDisplay> {
+        let index = self.package_list.selected()?;
+        self.visible_packages().get(index).copied()
+    }
+
+    fn current_locked(&self) -> Option<&PackageItemDisplay> {
+        self.locked.get(self.locked_list.selected()?)
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let visible = self.visible_packages().len();
+        let (list, len) = match self.focus {
+            Pane::Sources => (&mut self.source_list, self.sources.len() + 1),
+            Pane::Packages => (&mut self.package_list, visible),
+            Pane::Locked => (&mut self.locked_list, self.locked.len()),
+            Pane::Log => {
+                let scroll = self.log_scroll as isize - delta;
+                self.log_scroll = scroll.clamp(0, self.log.len() as isize) as usize;
+                return;
+            }
+        };
+        if let Some(current) = list.selected() {
+            let last = len.saturating_sub(1) as isize;
+            list.select(Some((current as isize + delta).clamp(0, last) as usize));
+        }
+        if self.focus == Pane::Sources {
+            let len = self.visible_packages().len();
+            self.package_list.select(Some(0));
+            clamp(&mut self.package_list, len);
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [title_area, main_area, log_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.size());
+        let [left_area, packages_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(main_area);
+        let [sources_area, locked_area] =
+            Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(left_area);
+
+        let mut title = vec![
+            Span::styled("npmpink ", fg(Color::Pink).add_modifier(Modifier::BOLD)),
+            Span::raw(&self.target),
+        ];
+        if let Some(ref busy) = self.busy {
+            title.push(Span::styled(format!("  {}…", busy), fg(Color::Yellow)));
+        } else if let Some(ref error) = self.error {
+            title.push(Span::styled(format!("  {}", error), fg(Color::Red)));
+        }
+        frame.render_widget(Paragraph::new(Line::from(title)), title_area);
+
+        self.render_sources(frame, sources_area);
+        self.render_locked(frame, locked_area);
+        self.render_packages(frame, packages_area);
+        self.render_log(frame, log_area);
+
+        let help = "tab:pane  j/k:move  a:add  d:remove  s:sync  u:unsync  r:reload  q:quit";
+        frame.render_widget(
+            Paragraph::new(Span::styled(help, fg(Color::Gray))),
+            help_area,
+        );
+    }
+
+    fn block(&self, pane: Pane, title: String) -> Block<'static> {
+        let color = if self.focus == pane {
+            Color::Blue
+        } else {
+            Color::Gray
+        };
+        Block::new()
+            .borders(Borders::ALL)
+            .border_style(fg(color))
+            .title(title)
+    }
+
+    fn render_sources(&mut self, frame: &mut Frame, area: Rect) {
+        let width = self
+            .sources
+            .iter()
+            .map(|s| s.label.chars().count())
+            .max()
+            .unwrap_or_default();
+        let mut rows = vec![ListItem::new(Span::styled(
+            "all sources",
+            fg(Color::GrayLight),
+        ))];
+        rows.extend(self.sources.iter().map(|source| {
+            let mut spans = vec![
+                Span::styled(
+                    format!("{:<width$}  ", source.label, width = width),
+                    fg(Color::Blue),
+                ),
+                Span::styled(source.kind.clone(), fg(Color::GrayLight)),
+            ];
+            if source.health != SourceHealth::Ok {
+                spans.push(Span::styled(format!("  {}", source.health), fg(Color::Red)));
+            }
+            ListItem::new(Line::from(spans))
+        }));
+
+        let block = self.block(Pane::Sources, format!(" Sources ({}) ", self.sources.len()));
+        frame.render_stateful_widget(list(rows, block), area, &mut self.source_list);
+    }
+
+    fn render_packages(&mut self, frame: &mut Frame, area: Rect) {
+        let visible = self.visible_packages();
+        let columns = PackageColumns::fit(visible.iter().copied());
+        let rows = visible
+            .iter()
+            .map(|pkg| package_row(&columns, pkg, false))
+            .collect::<Vec<ListItem>>();
+
+        let block = self.block(Pane::Packages, format!(" Packages ({}) ", rows.len()));
+        frame.render_stateful_widget(list(rows, block), area, &mut self.package_list);
+    }
+
+    fn render_locked(&mut self, frame: &mut Frame, area: Rect) {
+        let columns = PackageColumns::fit(&self.locked);
+        let rows = self
+            .locked
+            .iter()
+            .map(|pkg| package_row(&columns, pkg, true))
+            .collect::<Vec<ListItem>>();
+        let synced = self
+            .locked
+            .iter()
+            .filter(|pkg| pkg.status == PackageStatus::Linked)
+            .count();
+
+        let block = self.block(
+            Pane::Locked,
+            format!(" npmpink.lock ({}/{} synced) ", synced, self.locked.len()),
+        );
+        frame.render_stateful_widget(list(rows, block), area, &mut self.locked_list);
+    }
+
+    fn render_log(&mut self, frame: &mut Frame, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let end = self.log.len().saturating_sub(self.log_scroll);
+        let lines = self.log[end.saturating_sub(height)..end]
+            .iter()
+            .map(|line| {
+                let (label, color) = match line.level {
+                    LogLevel::Info => ("info ", Color::Green),
+                    LogLevel::Warn => ("warn ", Color::Yellow),
+                    LogLevel::Error => ("error", Color::Red),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{} ", label), fg(color)),
+                    Span::raw(line.message.clone()),
+                ])
+            })
+            .collect::<Vec<Line>>();
+
+        let block = self.block(Pane::Log, " Log ".to_owned());
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+/// Row of package columns, rows of the lockfile show the sync status after
+/// the version since the pane is narrow.
+fn package_row<'a>(
+    columns: &PackageColumns,
+    pkg: &PackageItemDisplay,
+    locked: bool,
+) -> ListItem<'a> {
+    let [title, version, source_label, path, _] = columns.cells(pkg);
+    let mut spans = vec![
+        Span::raw(format!("{}  ", title)),
+        Span::styled(format!("{}  ", version), fg(Color::GrayLight)),
+    ];
+    if locked {
+        spans.push(match pkg.status {
+            PackageStatus::Linked => Span::styled("linked", fg(Color::Green)),
+            _ => Span::styled("not synced", fg(Color::Yellow)),
+        });
+    } else {
+        spans.extend([
+            Span::styled(format!("{}  ", source_label), fg(Color::Blue)),
+            Span::styled(format!("{}  ", path), fg(Color::GrayLight)),
+            Span::styled(pkg.status.label(), fg(Color::Green)),
+        ]);
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn list<'a>(rows: Vec<ListItem<'a>>, block: Block<'a>) -> List<'a> {
+    List::new(rows)
+        .block(block)
+        .highlight_symbol("› ")
+        .highlight_style(Style::new().add_modifier(Modifier::BOLD))
+}
+
+/// Keep the cursor in the list of len items.
+fn clamp(list: &mut ListState, len: usize) {
+    list.select(match (list.selected(), len) {
+        (_, 0) => None,
+        (Some(i), _) => Some(i.min(len - 1)),
+        (None, _) => Some(0),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeActions {
+        calls: Vec<String>,
+        invalid_config: bool,
+    }
+
+    fn package(name: &str, source_id: &str, status: PackageStatus) -> PackageItemDisplay {
+        PackageItemDisplay {
+            title: name.into(),
+            version: "1.0.0".into(),
+            source_label: format!("libs-{}", source_id),
+            path: ".".into(),
+            status,
+            source_id: source_id.into(),
+        }
+    }
+
+    impl DashboardActions for FakeActions {
+        fn target(&self) -> String {
+            "/code/web".into()
+        }
+        fn sources(&mut self) -> Result<Vec<SourceItemDisplay>> {
+            if self.invalid_config {
+                anyhow::bail!("invalid config");
+            }
+            Ok(["1", "2"]
+                .map(|id| SourceItemDisplay {
+                    title: format!("/code/libs-{}", id),
+                    label: format!("libs-{}", id),
+                    kind: "dir".into(),
+                    health: SourceHealth::Ok,
+                    id: id.into(),
+                })
+                .to_vec())
+        }
+        fn packages(&mut self) -> Result<Vec<PackageItemDisplay>> {
+            Ok(vec![
+                package("@acme/ui", "1", PackageStatus::Linked),
+                package("@acme/utils", "2", PackageStatus::Available),
+            ])
+        }
+        fn rescan(&mut self) {
+            self.calls.push("rescan".into());
+        }
+        fn locked(&mut self) -> Result<Vec<PackageItemDisplay>> {
+            Ok(vec![package("@acme/ui", "1", PackageStatus::Linked)])
+        }
+        fn add(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+            self.calls.push(format!("add {}", pkg.title));
+            Ok(())
+        }
+        fn remove(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+            self.calls.push(format!("remove {}", pkg.title));
+            Ok(())
+        }
+        fn sync(&mut self) -> Result<()> {
+            anyhow::bail!("pnpm not found")
+        }
+        fn unsync(&mut self, pkg: &PackageItemDisplay) -> Result<()> {
+            self.calls.push(format!("unsync {}", pkg.title));
+            Ok(())
+        }
+    }
+
+    fn press(state: &mut DashboardState, actions: &mut FakeActions, code: KeyCode) {
+        if let Action::Run(task) = state.handle_key(KeyEvent::from(code)) {
+            state.execute(task, actions);
+        }
+    }
+
+    #[test]
+    fn test_dashboard_keys() {
+        let mut actions = FakeActions::default();
+        let mut state = DashboardState::new(&mut actions).unwrap();
+
+        // packages of the second source only.
+        state.focus = Pane::Sources;
+        press(&mut state, &mut actions, KeyCode::Char('j'));
+        press(&mut state, &mut actions, KeyCode::Char('j'));
+        assert_eq!(state.visible_packages().len(), 1);
+        press(&mut state, &mut actions, KeyCode::Tab);
+        press(&mut state, &mut actions, KeyCode::Char('a'));
+        // keys of other panes do nothing.
+        press(&mut state, &mut actions, KeyCode::Char('u'));
+        press(&mut state, &mut actions, KeyCode::Tab);
+        press(&mut state, &mut actions, KeyCode::Char('u'));
+        press(&mut state, &mut actions, KeyCode::Char('d'));
+        assert_eq!(
+            actions.calls,
+            ["add @acme/utils", "unsync @acme/ui", "remove @acme/ui"]
+        );
+
+        press(&mut state, &mut actions, KeyCode::Char('s'));
+        let last = state.log.last().unwrap();
+        assert_eq!(last.level, LogLevel::Error);
+        assert_eq!(last.message, "pnpm not found");
+
+        // sources of an invalid config are kept, the error is shown instead.
+        actions.invalid_config = true;
+        press(&mut state, &mut actions, KeyCode::Char('r'));
+        assert_eq!(actions.calls.last().unwrap(), "rescan");
+        assert_eq!(state.sources.len(), 2);
+        assert_eq!(state.error.as_deref(), Some("invalid config"));
+        actions.invalid_config = false;
+        press(&mut state, &mut actions, KeyCode::Char('r'));
+        assert!(state.error.is_none());
+        assert!(matches!(
+            state.handle_key(KeyEvent::from(KeyCode::Char('q'))),
+            Action::Quit
+        ));
+    }
+}
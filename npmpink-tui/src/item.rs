@@ -1,35 +1,157 @@
+use crate::select::PickItem;
 use anstyle::{AnsiColor, Style};
 use std::fmt;
 
 use npmpink_core::{
     item_display::PackageItemDisplay as PackageItemDisplayInner,
-    item_formatter::PackageItemFormatter,
+    item_display::SourceItemDisplay as SourceItemDisplayInner,
+    item_formatter::{PackageColumns, PackageItemFormatter, SourceItemFormatter},
+    ops::discover::SourceCandidate,
+    source::SourceHealth,
 };
 
 #[derive(Clone)]
 pub struct PackageItemDisplay<'a> {
     pub inner: PackageItemDisplayInner,
     pub raw: PackageItemFormatter<'a>,
+    pub columns: PackageColumns,
+    /// Group items under headers of their sources in pickers.
+    pub grouped: bool,
 }
 
 impl<'a> PackageItemDisplay<'a> {
-    pub fn new(formatter: PackageItemFormatter<'a>) -> PackageItemDisplay {
+    pub fn new(formatter: PackageItemFormatter<'a>) -> PackageItemDisplay<'a> {
+        let inner: PackageItemDisplayInner = formatter.clone().into();
         PackageItemDisplay {
-            inner: formatter.clone().into(),
+            columns: PackageColumns::fit([&inner]),
+            inner,
             raw: formatter,
+            grouped: false,
         }
     }
+
+    /// Items with columns aligned across all of them.
+    pub fn list(
+        formatters: Vec<PackageItemFormatter<'a>>,
+        grouped: bool,
+    ) -> Vec<PackageItemDisplay<'a>> {
+        let mut items = formatters
+            .into_iter()
+            .map(PackageItemDisplay::new)
+            .collect::<Vec<PackageItemDisplay>>();
+        let columns = PackageColumns::fit(items.iter().map(|item| &item.inner));
+        for item in items.iter_mut() {
+            item.columns = columns;
+            item.grouped = grouped;
+        }
+        items
+    }
 }
 
 impl<'a> fmt::Display for PackageItemDisplay<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [title, version, source_label, path, status] = self.columns.cells(&self.inner);
+        let dim_style = Style::new().fg_color(Some(AnsiColor::BrightBlack.into()));
         let source_label_style = Style::new().fg_color(Some(AnsiColor::Blue.into())).bold();
+        let status_style = Style::new().fg_color(Some(AnsiColor::Green.into()));
 
-        // TODO: apply style here
         write!(
             f,
-            "{}  {source_label_style}{}{source_label_style:#}",
-            self.inner.title, self.inner.source_label
-        )
+            "{title}  {dim_style}{version}{dim_style:#}  \
+             {source_label_style}{source_label}{source_label_style:#}  \
+             {dim_style}{path}{dim_style:#}"
+        )?;
+        if !status.is_empty() {
+            write!(f, "  {status_style}{status}{status_style:#}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> PickItem for PackageItemDisplay<'a> {
+    fn name(&self) -> String {
+        self.raw.inner.name.clone()
+    }
+
+    /// `<source id>:<package name>`, package names can't contain `:`.
+    fn preview_id(&self) -> Option<String> {
+        Some(format!(
+            "{}:{}",
+            self.raw.inner.source_id, self.raw.inner.name
+        ))
+    }
+
+    /// Path of the source.
+    fn group(&self) -> Option<String> {
+        if !self.grouped {
+            return None;
+        }
+        Some(self.raw.source.map_or("<removed source>".to_owned(), |s| {
+            s.path.display().to_string()
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct SourceItemDisplay<'a> {
+    pub inner: SourceItemDisplayInner,
+    pub raw: SourceItemFormatter<'a>,
+    /// Width of the path column.
+    pub width: usize,
+}
+
+impl<'a> SourceItemDisplay<'a> {
+    /// Items with the paths padded to the same width.
+    pub fn list(formatters: Vec<SourceItemFormatter<'a>>) -> Vec<SourceItemDisplay<'a>> {
+        let mut items = formatters
+            .into_iter()
+            .map(|formatter| SourceItemDisplay {
+                inner: formatter.clone().into(),
+                raw: formatter,
+                width: 0,
+            })
+            .collect::<Vec<SourceItemDisplay>>();
+        let width = items
+            .iter()
+            .map(|item| item.inner.title.chars().count())
+            .max()
+            .unwrap_or_default();
+        for item in items.iter_mut() {
+            item.width = width;
+        }
+        items
+    }
+}
+
+impl<'a> fmt::Display for SourceItemDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label_style = Style::new().fg_color(Some(AnsiColor::Blue.into())).bold();
+        let dim_style = Style::new().fg_color(Some(AnsiColor::BrightBlack.into()));
+        let health_style = Style::new().fg_color(Some(AnsiColor::Red.into()));
+
+        write!(
+            f,
+            "{:<width$}  {label_style}{}{label_style:#}  {dim_style}{}{dim_style:#}",
+            self.inner.title,
+            self.inner.label,
+            self.inner.kind,
+            width = self.width
+        )?;
+        if self.inner.health != SourceHealth::Ok {
+            write!(f, "  {health_style}{}{health_style:#}", self.inner.health)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> PickItem for SourceItemDisplay<'a> {
+    fn name(&self) -> String {
+        self.inner.title.clone()
+    }
+}
+
+impl PickItem for SourceCandidate {
+    fn name(&self) -> String {
+        self.dir.display().to_string()
     }
 }
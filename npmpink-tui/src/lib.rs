@@ -1,5 +1,6 @@
 pub mod color;
+pub mod dashboard;
 pub mod item;
-mod run;
 pub mod select;
 pub mod shell;
+mod terminal;
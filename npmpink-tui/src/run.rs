@@ -1,5 +0,0 @@
-use anyhow::Result;
-
-pub(crate) fn _run() -> Result<()> {
-    Ok(())
-}
@@ -0,0 +1,630 @@
+use super::skim::skim_match;
+use super::{PickItem, Picker, PreviewCommand};
+use crate::color::Color;
+use crate::terminal::{fg, TerminalGuard};
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{self, Stderr};
+use std::marker::PhantomData;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 6;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+/// Score and matched char positions of text for the query, none if it
+/// doesn't match.
+type Matcher = fn(&str, &str) -> Option<(i64, Vec<usize>)>;
+
+/// Options of the built-in picker, from the `"builtin"` key of config.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuiltinPickerConfig {
+    #[serde(skip_serializing_if = "BuiltinMatcher::is_default")]
+    pub matcher: BuiltinMatcher,
+}
+
+/// Algorithm to rank items by the query.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinMatcher {
+    /// Scoring like fzf.
+    #[default]
+    Fzf,
+    /// Scoring of skim, terms of the query must all match.
+    Skim,
+}
+
+impl BuiltinMatcher {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Picker drawn in the terminal by npmpink, used when fzf is not installed.
+pub struct FuzzyPicker<T> {
+    preview: Option<PreviewCommand>,
+    matcher: Matcher,
+    _marker: PhantomData<T>,
+}
+
+impl<T> FuzzyPicker<T> {
+    pub fn new(
+        config: Option<BuiltinPickerConfig>,
+        preview: Option<PreviewCommand>,
+    ) -> FuzzyPicker<T> {
+        let matcher: Matcher = match config.unwrap_or_default().matcher {
+            BuiltinMatcher::Fzf => fuzzy_match,
+            BuiltinMatcher::Skim => skim_match,
+        };
+        FuzzyPicker {
+            _marker: PhantomData,
+            preview,
+            matcher,
+        }
+    }
+}
+
+impl<T: PickItem> Picker for FuzzyPicker<T> {
+    type Item = T;
+
+    fn select(&self, items: &[Self::Item], selected: &[usize]) -> Result<Option<Vec<Self::Item>>> {
+        let texts = items
+            .iter()
+            .map(|item| {
+                self.format_item(item)
+                    .map(|s| strip_ansi(&s.to_string()))
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>();
+
+        let groups = items.iter().map(PickItem::group).collect();
+        let mut state = PickerState::new(texts, groups, self.matcher);
+        state.preselect(selected);
+        if let Some(ref preview) = self.preview {
+            state.preview = Some(Preview {
+                command: preview.clone(),
+                ids: items.iter().map(PickItem::preview_id).collect(),
+                outputs: HashMap::new(),
+            });
+        }
+        let picked = {
+            let _guard = TerminalGuard::enter()?;
+            let mut terminal = Terminal::new(CrosstermBackend::new(io::stderr()))?;
+            state.run(&mut terminal)?
+        };
+
+        Ok(picked.map(|picked| picked.into_iter().map(|i| items[i].clone()).collect()))
+    }
+
+    fn format_item(&self, item: &Self::Item) -> Option<Box<dyn Display>> {
+        Some(Box::new(item.to_string()))
+    }
+}
+
+/// Item matched by the query.
+struct Matched {
+    index: usize,
+    score: i64,
+    /// Char positions of the matched query chars.
+    positions: Vec<usize>,
+}
+
+struct PickerState {
+    texts: Vec<String>,
+    /// Header of items, items of same group are next to each other.
+    groups: Vec<Option<String>>,
+    matcher: Matcher,
+    query: String,
+    matched: Vec<Matched>,
+    selected: Vec<bool>,
+    /// Items were pre-selected, picking none is then deselecting all.
+    preselected: bool,
+    /// Cursor in matched items.
+    list: ListState,
+    /// Rendered rows, matched items and headers of groups.
+    rows: ListState,
+    preview: Option<Preview>,
+}
+
+struct Preview {
+    command: PreviewCommand,
+    ids: Vec<Option<String>>,
+    /// Output of items already previewed.
+    outputs: HashMap<usize, String>,
+}
+
+impl Preview {
+    fn output(&mut self, index: usize) -> &str {
+        let Preview {
+            command,
+            ids,
+            outputs,
+        } = self;
+        outputs.entry(index).or_insert_with(|| {
+            ids[index]
+                .as_ref()
+                .map(|id| strip_ansi(&command.output(id)))
+                .unwrap_or_default()
+        })
+    }
+}
+
+enum Action {
+    Continue,
+    Accept,
+    Abort,
+}
+
+impl PickerState {
+    fn new(texts: Vec<String>, groups: Vec<Option<String>>, matcher: Matcher) -> Self {
+        let selected = vec![false; texts.len()];
+        let mut state = PickerState {
+            texts,
+            groups,
+            matcher,
+            query: String::new(),
+            matched: Vec::new(),
+            selected,
+            preselected: false,
+            list: ListState::default(),
+            rows: ListState::default(),
+            preview: None,
+        };
+        state.filter();
+        state
+    }
+
+    /// Picked indexes, none if aborted.
+    fn run(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<Stderr>>,
+    ) -> Result<Option<Vec<usize>>> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match self.handle_key(key) {
+                Action::Continue => {}
+                Action::Accept => return Ok(Some(self.picked())),
+                Action::Abort => return Ok(None),
+            }
+        }
+    }
+
+    /// Key bindings follow fzf.
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Action::Abort,
+            KeyCode::Char('c' | 'g' | 'q') if ctrl => return Action::Abort,
+            KeyCode::Enter => return Action::Accept,
+            KeyCode::Tab => {
+                self.toggle();
+                self.move_cursor(1);
+            }
+            KeyCode::BackTab => {
+                self.toggle();
+                self.move_cursor(-1);
+            }
+            KeyCode::Up => self.move_cursor(-1),
+            KeyCode::Char('k' | 'p') if ctrl => self.move_cursor(-1),
+            KeyCode::Down => self.move_cursor(1),
+            KeyCode::Char('j' | 'n') if ctrl => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-10),
+            KeyCode::PageDown => self.move_cursor(10),
+            KeyCode::Char('a') if ctrl => self.select_all(true),
+            KeyCode::Char('d') if ctrl => self.select_all(false),
+            KeyCode::Char('u') if ctrl => self.set_query(String::new()),
+            KeyCode::Char('w') if ctrl => {
+                let trimmed = self.query.trim_end();
+                let word_start = trimmed.rfind(' ').map_or(0, |i| i + 1);
+                self.set_query(self.query[..word_start].to_owned());
+            }
+            KeyCode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            KeyCode::Char(c) if !ctrl => {
+                let query = format!("{}{}", self.query, c);
+                self.set_query(query);
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn set_query(&mut self, query: String) {
+        if query != self.query {
+            self.query = query;
+            self.filter();
+        }
+    }
+
+    fn filter(&mut self) {
+        self.matched = self
+            .texts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, text)| {
+                (self.matcher)(text, &self.query).map(|(score, positions)| Matched {
+                    index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        let texts = &self.texts;
+        let ranks = self.group_ranks();
+        // stable sort keeps the original order of items with same score.
+        self.matched
+            .sort_by_key(|m| (ranks[m.index], -m.score, texts[m.index].len()));
+        self.list.select(if self.matched.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    /// Rank of the group of each item, groups stay in order when sorted by
+    /// score.
+    fn group_ranks(&self) -> Vec<usize> {
+        let mut rank = 0;
+        (0..self.texts.len())
+            .map(|i| {
+                if i > 0 && self.groups[i] != self.groups[i - 1] {
+                    rank += 1;
+                }
+                rank
+            })
+            .collect()
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let Some(current) = self.list.selected() else {
+            return;
+        };
+        let last = self.matched.len().saturating_sub(1) as isize;
+        let next = (current as isize + delta).clamp(0, last);
+        self.list.select(Some(next as usize));
+    }
+
+    fn toggle(&mut self) {
+        if let Some(m) = self.list.selected().and_then(|i| self.matched.get(i)) {
+            self.selected[m.index] = !self.selected[m.index];
+        }
+    }
+
+    fn select_all(&mut self, selected: bool) {
+        for m in self.matched.iter() {
+            self.selected[m.index] = selected;
+        }
+    }
+
+    fn preselect(&mut self, indexes: &[usize]) {
+        for i in indexes {
+            self.selected[*i] = true;
+        }
+        self.preselected = !indexes.is_empty();
+    }
+
+    /// Selected items, or the one under cursor if nothing is selected and
+    /// nothing was pre-selected.
+    fn picked(&self) -> Vec<usize> {
+        let picked = (0..self.texts.len())
+            .filter(|i| self.selected[*i])
+            .collect::<Vec<usize>>();
+        if !picked.is_empty() || self.preselected {
+            return picked;
+        }
+        self.list
+            .selected()
+            .and_then(|i| self.matched.get(i))
+            .map(|m| vec![m.index])
+            .unwrap_or_default()
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [prompt_area, info_area, list_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .areas(frame.size());
+        let list_area = self.render_preview(frame, list_area);
+
+        let prompt = Line::from(vec![
+            Span::styled("> ", fg(Color::Blue)),
+            Span::raw(&self.query),
+        ]);
+        frame.render_widget(Paragraph::new(prompt), prompt_area);
+        frame.set_cursor(
+            prompt_area.x + 2 + self.query.chars().count() as u16,
+            prompt_area.y,
+        );
+
+        let selected_count = self.selected.iter().filter(|s| **s).count();
+        let info = Line::from(vec![
+            Span::styled(
+                format!(
+                    "  {}/{} ({}) ",
+                    self.matched.len(),
+                    self.texts.len(),
+                    selected_count
+                ),
+                fg(Color::Yellow),
+            ),
+            Span::styled(
+                "tab:select  s-tab:deselect  c-a:all  enter:accept  esc:cancel",
+                fg(Color::Gray),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(info), info_area);
+
+        let mut rows = Vec::new();
+        let mut cursor_row = None;
+        for (i, m) in self.matched.iter().enumerate() {
+            let group = self.groups[m.index].as_ref();
+            let prev_group = i
+                .checked_sub(1)
+                .and_then(|prev| self.groups[self.matched[prev].index].as_ref());
+            if let Some(header) = group.filter(|g| i == 0 || Some(*g) != prev_group) {
+                rows.push(ListItem::new(Line::styled(
+                    format!("── {} ", header),
+                    fg(Color::Blue).add_modifier(Modifier::BOLD),
+                )));
+            }
+            if self.list.selected() == Some(i) {
+                cursor_row = Some(rows.len());
+            }
+            rows.push({
+                let mut spans = vec![if self.selected[m.index] {
+                    Span::styled("◉ ", fg(Color::Teal))
+                } else {
+                    Span::styled("◯ ", fg(Color::GrayLight))
+                }];
+                spans.extend(self.texts[m.index].chars().enumerate().map(|(i, c)| {
+                    match m.positions.binary_search(&i) {
+                        Ok(_) => Span::styled(
+                            c.to_string(),
+                            fg(Color::Pink).add_modifier(Modifier::BOLD),
+                        ),
+                        Err(_) => Span::raw(c.to_string()),
+                    }
+                }));
+                ListItem::new(Line::from(spans))
+            });
+        }
+        // keep headers above the first items visible.
+        if cursor_row.is_some_and(|row| row <= 1) {
+            *self.rows.offset_mut() = 0;
+        }
+        self.rows.select(cursor_row);
+        let list = List::new(rows)
+            .highlight_symbol("› ")
+            .highlight_style(Style::new().add_modifier(Modifier::BOLD));
+        frame.render_stateful_widget(list, list_area, &mut self.rows);
+    }
+}
+
+impl PickerState {
+    /// Render preview of the item under cursor at right half of area,
+    /// returns the area left for the list.
+    fn render_preview(&mut self, frame: &mut Frame, area: Rect) -> Rect {
+        let Some(ref mut preview) = self.preview else {
+            return area;
+        };
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(area);
+
+        let output = self
+            .list
+            .selected()
+            .and_then(|i| self.matched.get(i))
+            .map(|m| preview.output(m.index))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(output).wrap(Wrap { trim: false }).block(
+                Block::new()
+                    .borders(Borders::LEFT)
+                    .border_style(fg(Color::Gray)),
+            ),
+            preview_area,
+        );
+
+        list_area
+    }
+}
+
+/// Score of text matching the query, and char positions of the matched
+/// chars, like fzf. Terms of the query separated by spaces must all match,
+/// matching is case sensitive only if the term has uppercase chars.
+pub(crate) fn fuzzy_match(text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut positions = Vec::new();
+
+    for term in query.split_whitespace() {
+        let (term_score, term_positions) = match_term(&chars, term)?;
+        score += term_score;
+        positions.extend(term_positions);
+    }
+    positions.sort_unstable();
+    positions.dedup();
+
+    Some((score, positions))
+}
+
+fn match_term(chars: &[char], term: &str) -> Option<(i64, Vec<usize>)> {
+    let case_sensitive = term.chars().any(char::is_uppercase);
+    let normalize = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    let term = term.chars().map(normalize).collect::<Vec<char>>();
+    let text = chars.iter().copied().map(normalize).collect::<Vec<char>>();
+    // most items don't match, skip the quadratic search for them.
+    let mut rest = text.iter();
+    if !term.iter().all(|tc| rest.any(|c| c == tc)) {
+        return None;
+    }
+    let bonus = (0..chars.len())
+        .map(|j| position_bonus(chars, j))
+        .collect::<Vec<i64>>();
+
+    // best[i][j]: best score of term[..=i] with term[i] matched at text[j].
+    let mut best = vec![vec![None::<i64>; text.len()]; term.len()];
+    let mut from = vec![vec![0usize; text.len()]; term.len()];
+
+    for (i, tc) in term.iter().enumerate() {
+        for j in 0..text.len() {
+            if text[j] != *tc {
+                continue;
+            }
+            if i == 0 {
+                best[i][j] = Some(SCORE_MATCH + bonus[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+                continue;
+            }
+            for k in (0..j).rev() {
+                let Some(prev) = best[i - 1][k] else {
+                    continue;
+                };
+                let gap = j - k - 1;
+                let score = prev
+                    + SCORE_MATCH
+                    + if gap == 0 {
+                        bonus[j].max(BONUS_CONSECUTIVE)
+                    } else {
+                        bonus[j] - PENALTY_GAP_START - PENALTY_GAP_EXTENSION * (gap as i64 - 1)
+                    };
+                if best[i][j].is_none_or(|s| score > s) {
+                    best[i][j] = Some(score);
+                    from[i][j] = k;
+                }
+            }
+        }
+    }
+
+    let last = term.len().checked_sub(1)?;
+    let (mut j, score) = best[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|(j, s)| (*s, std::cmp::Reverse(*j)))?;
+
+    let mut positions = vec![j];
+    for i in (1..=last).rev() {
+        j = from[i][j];
+        positions.push(j);
+    }
+    positions.reverse();
+
+    Some((score, positions))
+}
+
+/// Chars at start of words score higher, like `u` of `@acme/ui`.
+fn position_bonus(chars: &[char], j: usize) -> i64 {
+    let Some(prev) = j.checked_sub(1).map(|p| chars[p]) else {
+        return BONUS_BOUNDARY;
+    };
+    if matches!(prev, '/' | '-' | '_' | '.' | '@' | ' ' | ':') {
+        BONUS_BOUNDARY
+    } else if prev.is_lowercase() && chars[j].is_uppercase() {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+/// Items may be styled for fzf, which the picker draws itself.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert!(fuzzy_match("@acme/ui", "xyz").is_none());
+        assert_eq!(fuzzy_match("@acme/ui", ""), Some((0, vec![])));
+        assert_eq!(fuzzy_match("@acme/ui", "ui").unwrap().1, vec![6, 7]);
+        assert_eq!(fuzzy_match("@acme/ui", "AU"), None);
+
+        // boundary and consecutive matches score higher.
+        let score = |text| fuzzy_match(text, "ui").unwrap().0;
+        assert!(score("@acme/ui") > score("@acme/util-icons"));
+        assert!(score("@acme/util-icons") > score("build"));
+
+        // all terms must match.
+        let (_, positions) = fuzzy_match("@acme/ui  libs", "ui lib").unwrap();
+        assert_eq!(positions, vec![6, 7, 10, 11, 12]);
+        assert!(fuzzy_match("@acme/ui  libs", "ui app").is_none());
+    }
+
+    #[test]
+    fn test_picked_after_deselect_all() {
+        let texts = ["@acme/ui", "@acme/utils", "lodash"]
+            .map(String::from)
+            .to_vec();
+        // nothing selected, the cursor item is picked.
+        let state = PickerState::new(texts.clone(), vec![None; 3], fuzzy_match);
+        assert_eq!(state.picked(), [2]);
+
+        let mut state = PickerState::new(texts, vec![None; 3], fuzzy_match);
+        state.preselect(&[0, 1]);
+        assert_eq!(state.picked(), [0, 1]);
+        state.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        assert!(matches!(
+            state.handle_key(KeyEvent::from(KeyCode::Enter)),
+            Action::Accept
+        ));
+        assert!(state.picked().is_empty());
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("@acme/ui  \x1b[1m\x1b[34mlibs\x1b[0m"),
+            "@acme/ui  libs"
+        );
+    }
+}
@@ -1,53 +1,151 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::io::Write;
 use std::marker::PhantomData;
 use std::process::{Command, Stdio};
 use std::thread;
 
-use super::Picker;
+use super::{group_headers, PickItem, Picker, PreviewCommand};
+use anstyle::{AnsiColor, Style};
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
-pub struct FzfPickerConfig {}
+/// Key of group header lines, they can't be picked.
+const HEADER_KEY: &str = "-";
+
+/// Options of fzf, from the `"fzf"` key of config.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FzfPickerConfig {
+    /// `--height` like `40%`, fzf is full screen if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<FzfLayout>,
+    /// Extra key bindings, like `ctrl-a:select-all`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<String>,
+    /// Extra args passed to fzf as is, after the args of npmpink.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// `--layout` of fzf.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FzfLayout {
+    Default,
+    Reverse,
+    ReverseList,
+}
+
+impl FzfLayout {
+    fn as_arg(&self) -> &'static str {
+        match self {
+            FzfLayout::Default => "default",
+            FzfLayout::Reverse => "reverse",
+            FzfLayout::ReverseList => "reverse-list",
+        }
+    }
+}
 
 pub struct FzfPicker<T> {
     config: FzfPickerConfig,
+    preview: Option<PreviewCommand>,
     _marker: PhantomData<T>,
 }
 
 impl<T> FzfPicker<T> {
-    pub fn new(config: Option<FzfPickerConfig>) -> FzfPicker<T> {
+    pub fn new(config: Option<FzfPickerConfig>, preview: Option<PreviewCommand>) -> FzfPicker<T> {
         FzfPicker {
             _marker: PhantomData,
             config: config.unwrap_or_default(),
+            preview,
         }
     }
 }
 
-impl<T: Display + Clone> Picker for FzfPicker<T> {
+impl<T: PickItem> Picker for FzfPicker<T> {
     type Item = T;
 
-    fn select(&self, input_items: &[Self::Item]) -> Result<Vec<Self::Item>> {
-        let mut fzf_child = Command::new("fzf")
+    fn select(
+        &self,
+        input_items: &[Self::Item],
+        selected: &[usize],
+    ) -> Result<Option<Vec<Self::Item>>> {
+        let mut command = Command::new("fzf");
+        command
             .args([
                 "--multi",
                 "--bind",
                 r"tab:select+down,shift-tab:deselect+up",
             ])
-            .args(["--with-nth", "2.."])
+            // lines are `index\tpreview id\titem`.
+            .args(["--delimiter", "\t", "--with-nth", "3.."])
             .args(["--ansi"])
-            .args(["--header", "tab:select,s-tab:deselect,enter:accept"])
+            .args(["--header", "tab:select,s-tab:deselect,enter:accept"]);
+        if let Some(ref preview) = self.preview {
+            // header lines have no preview id.
+            let preview = format!("[ -z {{2}} ] || {}", preview.to_shell("{2}"));
+            command
+                .args(["--preview", &preview])
+                .args(["--preview-window", "right,50%,wrap"]);
+        }
+        // fzf prints the picked lines, they are mapped back to items by the
+        // key in the first field.
+        let keys = (0..input_items.len())
+            .map(|i| (i.to_string(), i))
+            .collect::<HashMap<String, usize>>();
+        let header_style = Style::new().bold().fg_color(Some(AnsiColor::Blue.into()));
+        let mut items = Vec::new();
+        let mut positions = Vec::new();
+        for ((i, x), header) in input_items
+            .iter()
+            .enumerate()
+            .zip(group_headers(input_items))
+        {
+            if let Some(header) = header {
+                items.push(format!(
+                    "{}\t\t{header_style}── {} {header_style:#}",
+                    HEADER_KEY,
+                    single_line(&header)
+                ));
+            }
+            positions.push(items.len());
+            items.push(format!(
+                "{}\t{}\t{}",
+                i,
+                x.preview_id().unwrap_or_default(),
+                single_line(&x.to_string())
+            ));
+        }
+
+        if !selected.is_empty() {
+            // needs fzf 0.36+ for the `load` event.
+            let toggles = selected
+                .iter()
+                .map(|i| format!("pos({})+toggle", positions[*i] + 1))
+                .collect::<Vec<String>>()
+                .join("+");
+            command.args(["--bind", &format!("load:{}+first", toggles)]);
+        }
+        if let Some(ref height) = self.config.height {
+            command.args(["--height", height]);
+        }
+        if let Some(layout) = self.config.layout {
+            command.args(["--layout", layout.as_arg()]);
+        }
+        for binding in self.config.bindings.iter() {
+            command.args(["--bind", binding]);
+        }
+        command.args(&self.config.args);
+        let mut fzf_child = command
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .spawn()?;
+            .spawn()
+            .context("failed to run fzf")?;
 
-        let mut feed_item_fd = fzf_child.stdin.take().expect("Failed to open fzf stdin");
-
-        let items = input_items
-            .iter()
-            .enumerate()
-            .map(|(i, x)| format!("{} {}", i, x))
-            .collect::<Vec<String>>();
+        let mut feed_item_fd = fzf_child.stdin.take().context("failed to open fzf stdin")?;
 
         thread::spawn(move || {
             for item in items.iter() {
@@ -55,20 +153,25 @@ impl<T: Display + Clone> Picker for FzfPicker<T> {
             }
         });
 
-        let output = fzf_child.wait_with_output().expect("Failed to read stdout");
-
-        let select_index = parse_selected_index(
-            String::from_utf8_lossy(&output.stdout)
-                .trim_end()
-                .to_owned(),
-        );
+        let output = fzf_child
+            .wait_with_output()
+            .context("failed to read fzf output")?;
+        match output.status.code() {
+            // 1 is no match.
+            Some(0 | 1) => {}
+            Some(130) => return Ok(None),
+            _ => bail!("fzf exited with {}", output.status),
+        }
 
-        let select_items = select_index
-            .iter()
-            .map(|i| input_items[*i].clone())
-            .collect::<Vec<Self::Item>>();
+        let select_items = selected_keys(&String::from_utf8_lossy(&output.stdout))
+            .map(|key| {
+                keys.get(key)
+                    .map(|i| input_items[*i].clone())
+                    .with_context(|| format!("fzf picked unknown item `{}`", key))
+            })
+            .collect::<Result<Vec<Self::Item>>>()?;
 
-        Ok(select_items)
+        Ok(Some(select_items))
     }
 
     fn format_item(&self, item: &Self::Item) -> Option<Box<dyn Display>> {
@@ -76,12 +179,35 @@ impl<T: Display + Clone> Picker for FzfPicker<T> {
     }
 }
 
-fn parse_selected_index(input: String) -> Vec<usize> {
-    input
+/// Keys of the lines printed by fzf, picked headers are left out.
+fn selected_keys(output: &str) -> impl Iterator<Item = &str> {
+    output
         .lines()
-        .map(|e| {
-            // split with whitespace and the first is index number
-            e.split_whitespace().next().unwrap().parse().unwrap()
-        })
-        .collect()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split('\t').next().unwrap_or_default())
+        .filter(|key| *key != HEADER_KEY)
+}
+
+/// Tabs and newlines of item would break the fields and lines read by fzf.
+fn single_line(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Whether `fzf` is an executable file in PATH.
+pub(crate) fn fzf_installed() -> bool {
+    let name = if cfg!(windows) { "fzf.exe" } else { "fzf" };
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selected_keys() {
+        let output = "2\t1:@acme/ui\t@acme/ui  1.0.0\n-\t\t── libs\n10\t\tlodash\n";
+        assert_eq!(selected_keys(output).collect::<Vec<&str>>(), ["2", "10"]);
+        assert_eq!(single_line("a\tb\nc"), "a b c");
+    }
 }
@@ -0,0 +1,102 @@
+use super::{PickItem, Picker};
+use anyhow::{bail, Context, Result};
+use std::fmt::Display;
+use std::fs;
+use std::io::Read;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+#[derive(Default)]
+pub struct InputPickerConfig {
+    /// File to read names from, stdin if none or `-`.
+    pub path: Option<PathBuf>,
+}
+
+/// Picks items by names read from file or stdin, one name per line, for
+/// scripts and CI where no terminal is attached.
+pub struct InputPicker<T> {
+    config: InputPickerConfig,
+    _marker: PhantomData<T>,
+}
+
+impl<T> InputPicker<T> {
+    pub fn new(config: Option<InputPickerConfig>) -> InputPicker<T> {
+        InputPicker {
+            _marker: PhantomData,
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    fn read_input(&self) -> Result<String> {
+        match self.config.path {
+            Some(ref path) if path.as_os_str() != "-" => fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display())),
+            _ => {
+                let mut input = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut input)
+                    .context("failed to read stdin")?;
+                Ok(input)
+            }
+        }
+    }
+}
+
+impl<T: PickItem> Picker for InputPicker<T> {
+    type Item = T;
+
+    /// The input is the whole selection, pre-selected items are ignored.
+    fn select(&self, items: &[Self::Item], _selected: &[usize]) -> Result<Option<Vec<Self::Item>>> {
+        let names = items.iter().map(PickItem::name).collect::<Vec<String>>();
+        let picked = pick_lines(&names, &self.read_input()?)?;
+
+        Ok(Some(picked.into_iter().map(|i| items[i].clone()).collect()))
+    }
+
+    fn format_item(&self, item: &Self::Item) -> Option<Box<dyn Display>> {
+        Some(Box::new(item.name()))
+    }
+}
+
+/// Indexes of names listed in input, blank lines and lines starting with
+/// `#` are skipped.
+fn pick_lines(names: &[String], input: &str) -> Result<Vec<usize>> {
+    let mut picked = vec![false; names.len()];
+
+    for line in input.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let matched = names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| *name == line)
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        match matched.len() {
+            0 => bail!("`{}` is not in the items to pick", line),
+            1 => picked[matched[0]] = true,
+            n => bail!("`{}` matches {} items", line, n),
+        }
+    }
+
+    Ok((0..names.len()).filter(|i| picked[*i]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_lines() {
+        let names = ["@acme/ui", "@acme/utils", "lodash", "lodash"].map(String::from);
+
+        let input = "# packages\n@acme/utils\n\n  @acme/ui \n@acme/utils\n";
+        assert_eq!(pick_lines(&names, input).unwrap(), vec![0, 1]);
+        assert!(pick_lines(&names, "").unwrap().is_empty());
+
+        let e = pick_lines(&names, "react").unwrap_err();
+        assert_eq!(e.to_string(), "`react` is not in the items to pick");
+        assert!(pick_lines(&names, "lodash").is_err());
+    }
+}
@@ -1,15 +1,53 @@
-use super::Picker;
+use super::{group_headers, PickItem, Picker};
 use crate::color::Color;
 use anyhow::Result;
 use inquire::ui::{
     Attributes, Color as UiColor, ErrorMessageRenderConfig, RenderConfig, StyleSheet, Styled,
 };
-use inquire::{formatter::MultiOptionFormatter, MultiSelect};
+use inquire::{formatter::MultiOptionFormatter, InquireError, MultiSelect};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::marker::PhantomData;
 
-#[derive(Default)]
-pub struct InquirePickerConfig {}
+const MAX_HEADER_WIDTH: usize = 24;
+
+/// Options of inquire, from the `"inquire"` key of config.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InquirePickerConfig {
+    /// Number of items shown at once, 7 if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+    #[serde(skip_serializing_if = "InquireColors::is_default")]
+    pub colors: InquireColors,
+}
+
+/// Theme colors, unset ones use the default theme.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InquireColors {
+    /// Prompt prefix, `blue` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<Color>,
+    /// Prefix of the option under cursor, `teal` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlighted: Option<Color>,
+    /// Selected options and checkboxes, `teal` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected: Option<Color>,
+    /// Unselected checkboxes and scroll indicators, `gray_light` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unselected: Option<Color>,
+    /// Answer after the prompt is done, `purple` by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer: Option<Color>,
+}
+
+impl InquireColors {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
 
 pub struct InquirePicker<T> {
     _marker: PhantomData<T>,
@@ -25,31 +63,61 @@ impl<T> InquirePicker<T> {
     }
 }
 
-impl<T: Display + Clone> Picker for InquirePicker<T> {
+impl<T: PickItem> Picker for InquirePicker<T> {
     type Item = T;
 
-    fn select(&self, items: &[Self::Item]) -> Result<Vec<Self::Item>> {
+    fn select(&self, items: &[Self::Item], selected: &[usize]) -> Result<Option<Vec<Self::Item>>> {
         let formatter: MultiOptionFormatter<'_, String> = &|a| format!("{} selected", a.len());
+        // options can't be headers, the group is shown before the first
+        // option of group instead.
+        let headers = group_headers(items)
+            .into_iter()
+            .map(|h| h.map(|h| truncate_start(&h, MAX_HEADER_WIDTH)))
+            .collect::<Vec<Option<String>>>();
+        let header_width = headers.iter().flatten().map(|h| h.chars().count()).max();
         let opts: Vec<String> = items
             .iter()
-            .map(|p| {
-                self.format_item(p)
+            .zip(headers.iter())
+            .map(|(p, header)| {
+                let text = self
+                    .format_item(p)
                     .map(|p| p.to_string())
-                    .unwrap_or("".to_string())
+                    .unwrap_or("".to_string());
+                match header_width {
+                    Some(width) => format!(
+                        "{:<width$} │ {}",
+                        header.as_deref().unwrap_or_default(),
+                        text
+                    ),
+                    None => text,
+                }
             })
             .collect();
-        let theme = create_theme();
+        let theme = create_theme(&self.config.colors);
 
-        let ans = MultiSelect::new("Select packages:", opts)
+        let mut prompt = MultiSelect::new("Select packages:", opts);
+        if let Some(page_size) = self.config.page_size {
+            prompt = prompt.with_page_size(page_size);
+        }
+        let ans = match prompt
             .with_render_config(theme)
             .with_formatter(formatter)
-            .raw_prompt()?;
+            .with_default(selected)
+            .raw_prompt()
+        {
+            Ok(ans) => ans,
+            Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
 
-        Ok(ans
-            .into_iter()
-            .filter_map(|n| items.get(n.index))
-            .cloned()
-            .collect::<Vec<T>>())
+        Ok(Some(
+            ans.into_iter()
+                .filter_map(|n| items.get(n.index))
+                .cloned()
+                .collect::<Vec<T>>(),
+        ))
     }
 
     fn format_item(&self, item: &Self::Item) -> Option<Box<dyn Display>> {
@@ -57,24 +125,40 @@ impl<T: Display + Clone> Picker for InquirePicker<T> {
     }
 }
 
+/// Last chars of text, like the end of a path, prefixed by `…` if cut.
+fn truncate_start(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count <= width {
+        return text.to_owned();
+    }
+    let tail = text.chars().skip(count - width + 1).collect::<String>();
+    format!("…{}", tail)
+}
+
 fn rgb(color: Color) -> UiColor {
     UiColor::AnsiValue(color as u8)
 }
 
-fn create_theme() -> RenderConfig<'static> {
+fn create_theme(colors: &InquireColors) -> RenderConfig<'static> {
+    let prompt = colors.prompt.unwrap_or(Color::Blue);
+    let highlighted = colors.highlighted.unwrap_or(Color::Teal);
+    let selected = colors.selected.unwrap_or(Color::Teal);
+    let unselected = colors.unselected.unwrap_or(Color::GrayLight);
+    let answer = colors.answer.unwrap_or(Color::Purple);
+
     RenderConfig::empty()
         .with_default_value(StyleSheet::new().with_fg(rgb(Color::Pink)))
         .with_answer(
             StyleSheet::new()
-                .with_fg(rgb(Color::Purple))
+                .with_fg(rgb(answer))
                 .with_attr(Attributes::BOLD),
         )
         // Prefixes
-        .with_prompt_prefix(Styled::new("›").with_fg(rgb(Color::Blue)))
+        .with_prompt_prefix(Styled::new("›").with_fg(rgb(prompt)))
         .with_answered_prompt_prefix(Styled::new("✔").with_fg(rgb(Color::Green)))
-        .with_scroll_up_prefix(Styled::new("▴").with_fg(rgb(Color::GrayLight)))
-        .with_scroll_down_prefix(Styled::new("▾").with_fg(rgb(Color::GrayLight)))
-        .with_highlighted_option_prefix(Styled::new("›").with_fg(rgb(Color::Teal)))
+        .with_scroll_up_prefix(Styled::new("▴").with_fg(rgb(unselected)))
+        .with_scroll_down_prefix(Styled::new("▾").with_fg(rgb(unselected)))
+        .with_highlighted_option_prefix(Styled::new("›").with_fg(rgb(highlighted)))
         // States
         .with_help_message(StyleSheet::new().with_fg(rgb(Color::Purple)))
         .with_error_message(
@@ -84,7 +168,7 @@ fn create_theme() -> RenderConfig<'static> {
         )
         .with_canceled_prompt_indicator(Styled::new("(skipped)").with_fg(rgb(Color::Gray)))
         // Selects
-        .with_selected_option(Some(StyleSheet::new().with_fg(rgb(Color::Teal))))
-        .with_selected_checkbox(Styled::new("◉").with_fg(rgb(Color::Teal)))
-        .with_unselected_checkbox(Styled::new("◯").with_fg(rgb(Color::GrayLight)))
+        .with_selected_option(Some(StyleSheet::new().with_fg(rgb(selected))))
+        .with_selected_checkbox(Styled::new("◉").with_fg(rgb(selected)))
+        .with_unselected_checkbox(Styled::new("◯").with_fg(rgb(unselected)))
 }
@@ -1,30 +1,122 @@
+#[path = "fuzzy.rs"]
+mod fuzzy_picker;
 #[path = "fzf.rs"]
 mod fzf_picker;
+#[path = "input.rs"]
+mod input_picker;
 #[path = "inquire.rs"]
 mod inquire_picker;
+mod skim;
 
 use anyhow::Result;
-use fzf_picker::{FzfPicker, FzfPickerConfig};
-pub use inquire_picker::{InquirePicker, InquirePickerConfig};
+pub use fuzzy_picker::{BuiltinMatcher, BuiltinPickerConfig, FuzzyPicker};
+use fzf_picker::{fzf_installed, FzfPicker};
+pub use fzf_picker::{FzfLayout, FzfPickerConfig};
+pub use input_picker::{InputPicker, InputPickerConfig};
+pub use inquire_picker::{InquireColors, InquirePicker, InquirePickerConfig};
+use npmpink_core::item_formatter::group_order;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::Command;
 
 trait Picker {
     type Item: Display;
 
-    fn select(&self, items: &[Self::Item]) -> Result<Vec<Self::Item>>;
+    /// Items picked with `selected` indexes pre-selected, none if the
+    /// picker is cancelled.
+    fn select(&self, items: &[Self::Item], selected: &[usize]) -> Result<Option<Vec<Self::Item>>>;
 
     fn format_item(&self, item: &Self::Item) -> Option<Box<dyn Display>>;
 }
 
+/// Item of pickers.
+pub trait PickItem: Display + Clone {
+    /// Name to pick the item by in `Input` mode.
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    /// Id passed to the preview command, none if the item has no preview.
+    fn preview_id(&self) -> Option<String> {
+        None
+    }
+
+    /// Header to list the item under, items of same group are listed
+    /// together.
+    fn group(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Header of the group that starts at each item, none for the other items
+/// of the group, the items are ordered by group.
+fn group_headers<I: PickItem>(items: &[I]) -> Vec<Option<String>> {
+    let mut prev = None;
+    items
+        .iter()
+        .map(|item| {
+            let group = item.group();
+            let header = group.clone().filter(|_| group != prev);
+            prev = group;
+            header
+        })
+        .collect()
+}
+
+/// Command that prints details of the highlighted item, the preview id of
+/// the item is appended to args.
+#[derive(Debug, Clone)]
+pub struct PreviewCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl PreviewCommand {
+    /// Output of the command, or it's error.
+    pub(crate) fn output(&self, id: &str) -> String {
+        match Command::new(&self.program)
+            .args(&self.args)
+            .arg(id)
+            .output()
+        {
+            Ok(output) if output.status.success() => {
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+            Ok(output) => String::from_utf8_lossy(&output.stderr).into_owned(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    /// Shell command line with `placeholder` as the id, like `{2}` of fzf.
+    pub(crate) fn to_shell(&self, placeholder: &str) -> String {
+        std::iter::once(self.program.to_string_lossy().into_owned())
+            .chain(self.args.iter().cloned())
+            .map(|arg| shell_quote(&arg))
+            .chain(std::iter::once(placeholder.to_owned()))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
 /// The list pickers that this crate supports.
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum PickerMode {
     Inquire,
-    // TODO: use which crate to detect existence.
+    /// Falls back to `Builtin` if fzf is not installed.
     #[default]
     Fzf,
+    /// Fuzzy picker of npmpink, works without fzf.
+    Builtin,
+    /// Read names to pick from file or stdin, used when stdin or stdout is
+    /// not a terminal.
+    Input,
 }
 
 #[derive(Default)]
@@ -32,14 +124,102 @@ pub struct PickConfig {
     pub mode: PickerMode,
     pub fzf: Option<FzfPickerConfig>,
     pub inquire: Option<InquirePickerConfig>,
+    pub builtin: Option<BuiltinPickerConfig>,
+    pub input: Option<InputPickerConfig>,
+    /// Preview of items, inquire doesn't support it.
+    pub preview: Option<PreviewCommand>,
 }
 
-pub fn pick_items<I: Display + Clone>(items: &[I], config: Option<PickConfig>) -> Result<Vec<I>> {
+pub fn pick_items<I: PickItem>(items: &[I], config: Option<PickConfig>) -> Result<Vec<I>> {
+    Ok(pick_items_with_selected(items, &[], config)?.unwrap_or_default())
+}
+
+/// Pick items with the `selected` indexes pre-selected, none if the picker
+/// is cancelled, so it's not mistaken for deselecting all.
+pub fn pick_items_with_selected<I: PickItem>(
+    items: &[I],
+    selected: &[usize],
+    config: Option<PickConfig>,
+) -> Result<Option<Vec<I>>> {
+    // list items of same group together, the order of picked items follows.
+    let order = group_order(items, PickItem::group);
+    let items = order.iter().map(|i| items[*i].clone()).collect::<Vec<I>>();
+    let selected = order
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| selected.contains(i))
+        .map(|(position, _)| position)
+        .collect::<Vec<usize>>();
+
     let config = config.unwrap_or_default();
-    let picker: Box<dyn Picker<Item = I>> = match config.mode {
+    let preview = config
+        .preview
+        .filter(|_| items.iter().any(|item| item.preview_id().is_some()));
+    let mode = if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+        config.mode
+    } else {
+        PickerMode::Input
+    };
+    let picker: Box<dyn Picker<Item = I>> = match mode {
+        PickerMode::Input => Box::new(InputPicker::new(config.input)),
         PickerMode::Inquire => Box::new(InquirePicker::new(config.inquire)),
-        PickerMode::Fzf => Box::new(FzfPicker::new(config.fzf)),
+        PickerMode::Fzf if fzf_installed() => Box::new(FzfPicker::new(config.fzf, preview)),
+        PickerMode::Fzf | PickerMode::Builtin => {
+            Box::new(FuzzyPicker::new(config.builtin, preview))
+        }
     };
 
-    picker.select(items)
+    picker.select(&items, &selected)
+}
+
+/// Ask yes or no, default to no.
+pub fn confirm(message: &str) -> Result<bool> {
+    Ok(inquire::Confirm::new(message)
+        .with_default(false)
+        .prompt()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Grouped(&'static str, &'static str);
+
+    impl Display for Grouped {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl PickItem for Grouped {
+        fn group(&self) -> Option<String> {
+            Some(self.1.to_owned())
+        }
+    }
+
+    #[test]
+    fn test_group_headers() {
+        let items = [
+            Grouped("ui", "libs"),
+            Grouped("utils", "libs"),
+            Grouped("app", "apps"),
+        ];
+        assert_eq!(
+            group_headers(&items),
+            [Some("libs".to_owned()), None, Some("apps".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_preview_command_to_shell() {
+        let command = PreviewCommand {
+            program: PathBuf::from("/opt/npk"),
+            args: vec!["--cwd".into(), "/tmp/it's".into(), "__preview".into()],
+        };
+        assert_eq!(
+            command.to_shell("{2}"),
+            r"'/opt/npk' '--cwd' '/tmp/it'\''s' '__preview' {2}"
+        );
+    }
 }
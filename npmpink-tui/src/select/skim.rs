@@ -0,0 +1,43 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref MATCHER: SkimMatcherV2 = SkimMatcherV2::default().smart_case();
+}
+
+/// Scoring of the built-in picker with the `skim` matcher, like skim, terms
+/// of the query separated by spaces must all match.
+pub(crate) fn skim_match(text: &str, query: &str) -> Option<(i64, Vec<usize>)> {
+    let mut score = 0;
+    let mut positions = Vec::new();
+
+    for term in query.split_whitespace() {
+        let (term_score, term_positions) = MATCHER.fuzzy_indices(text, term)?;
+        score += term_score;
+        positions.extend(term_positions);
+    }
+    positions.sort_unstable();
+    positions.dedup();
+
+    Some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skim_match() {
+        assert_eq!(skim_match("@acme/ui", ""), Some((0, vec![])));
+        assert!(skim_match("@acme/ui", "xyz").is_none());
+        assert_eq!(skim_match("@acme/ui", "ui").unwrap().1, vec![6, 7]);
+        assert!(skim_match("@acme/ui", "UI").is_none());
+
+        let score = |text| skim_match(text, "ui").unwrap().0;
+        assert!(score("@acme/ui") > score("@acme/utils-icons"));
+
+        assert!(skim_match("@acme/ui  libs", "ui lib").is_some());
+        assert!(skim_match("@acme/ui  libs", "ui app").is_none());
+    }
+}
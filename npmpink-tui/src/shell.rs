@@ -1,6 +1,7 @@
 use anstyle::{AnsiColor, Style};
 use anyhow::{Error, Result};
 use lazy_static::lazy_static;
+use serde::Serialize;
 use std::fmt::Display;
 use std::io::prelude::*;
 use std::sync::{Arc, RwLock, RwLockWriteGuard};
@@ -21,9 +22,28 @@ pub fn shell<'a>() -> Result<RwLockWriteGuard<'a, Shell>> {
         .map_err(|err| Error::msg(err.to_string()))
 }
 
+/// In JSON mode stdout only gets the JSON output of command, info and print
+/// messages are dropped and warnings go to stderr.
 #[derive(Debug)]
 pub struct Shell {
     out: ShellOut,
+    json: bool,
+    json_printed: bool,
+    /// Messages collected instead of printed, see `start_log`.
+    log: Option<Vec<LogLine>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub message: String,
 }
 
 impl Default for Shell {
@@ -39,13 +59,68 @@ impl Shell {
                 stdout: std::io::stdout(),
                 stderr: std::io::stderr(),
             },
+            json: false,
+            json_printed: false,
+            log: None,
+        }
+    }
+    /// Collect messages instead of printing them, for full screen UIs that
+    /// own the terminal.
+    pub fn start_log(&mut self) {
+        self.log.get_or_insert_with(Vec::new);
+    }
+    pub fn stop_log(&mut self) {
+        self.log = None;
+    }
+    pub fn is_logging(&self) -> bool {
+        self.log.is_some()
+    }
+    /// Messages collected since the last call.
+    pub fn take_log(&mut self) -> Vec<LogLine> {
+        self.log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+    /// Whether the message is logged, blank messages are dropped.
+    fn push_log<T: Display>(&mut self, level: LogLevel, msg: T) -> bool {
+        let Some(ref mut log) = self.log else {
+            return false;
+        };
+        let message = msg.to_string().trim().to_owned();
+        if !message.is_empty() {
+            log.push(LogLine { level, message });
         }
+        true
+    }
+    pub fn set_json(&mut self, json: bool) {
+        self.json = json;
+    }
+    pub fn is_json(&self) -> bool {
+        self.json
+    }
+    /// Whether the command has printed it's JSON output.
+    pub fn is_json_printed(&self) -> bool {
+        self.json_printed
+    }
+    /// Print value as the JSON output of command, does nothing if not in
+    /// JSON mode.
+    pub fn json<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        if !self.json {
+            return Ok(());
+        }
+        writeln!(self.out.stdout, "{}", serde_json::to_string_pretty(value)?)?;
+        self.json_printed = true;
+        Ok(())
     }
     pub fn error<T: Display>(&mut self, msg: T) -> Result<()> {
-        self.out.write_stderr(&"ERROR", &msg, None)?;
+        if self.push_log(LogLevel::Error, &msg) {
+            return Ok(());
+        }
+        self.out.write_stderr(&"ERROR", &msg, None, None)?;
         Ok(())
     }
     pub fn info<T: Display>(&mut self, msg: T) -> Result<()> {
+        if self.json || self.push_log(LogLevel::Info, &msg) {
+            return Ok(());
+        }
         self.out.write_stdout(
             &"INFO",
             &msg,
@@ -59,18 +134,26 @@ impl Shell {
         )?;
         Ok(())
     }
+    /// Print message as is, without prefix and style.
+    pub fn print<T: Display>(&mut self, msg: T) -> Result<()> {
+        if self.json || self.push_log(LogLevel::Info, &msg) {
+            return Ok(());
+        }
+        writeln!(self.out.stdout, "{}", msg)?;
+        Ok(())
+    }
     pub fn warn<T: Display>(&mut self, msg: T) -> Result<()> {
-        self.out.write_stdout(
-            &"WARN",
-            &msg,
-            Some(
-                Style::new()
-                    .bold()
-                    .bg_color(Some(AnsiColor::Yellow.into()))
-                    .fg_color(Some(AnsiColor::White.into())),
-            ),
-            None,
-        )?;
+        let style = Style::new()
+            .bold()
+            .bg_color(Some(AnsiColor::Yellow.into()))
+            .fg_color(Some(AnsiColor::White.into()));
+        if self.push_log(LogLevel::Warn, &msg) {
+            return Ok(());
+        }
+        if self.json {
+            return self.out.write_stderr(&"WARN", &msg, Some(style), None);
+        }
+        self.out.write_stdout(&"WARN", &msg, Some(style), None)?;
         Ok(())
     }
 }
@@ -86,12 +169,15 @@ impl ShellOut {
         &mut self,
         prefix: &dyn Display,
         msg: &dyn Display,
+        prefix_style: Option<Style>,
         style: Option<Style>,
     ) -> Result<()> {
-        let bold = Style::new()
-            .bold()
-            .bg_color(Some(AnsiColor::Red.into()))
-            .fg_color(Some(AnsiColor::White.into()));
+        let bold = prefix_style.unwrap_or(
+            Style::new()
+                .bold()
+                .bg_color(Some(AnsiColor::Red.into()))
+                .fg_color(Some(AnsiColor::White.into())),
+        );
         let style = style.unwrap_or_default();
 
         let mut buffer = Vec::new();
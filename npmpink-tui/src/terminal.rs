@@ -0,0 +1,30 @@
+use crate::color::Color;
+use anyhow::Result;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::ExecutableCommand;
+use ratatui::style::{Color as UiColor, Style};
+use std::io;
+
+/// Raw mode and alternate screen of stderr, restored when dropped even if
+/// the UI fails.
+pub(crate) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(crate) fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        io::stderr().execute(EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = io::stderr().execute(LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub(crate) fn fg(color: Color) -> Style {
+    Style::new().fg(UiColor::Indexed(color as u8))
+}